@@ -0,0 +1,96 @@
+// Compares search engine with the naive search it replaced: cargo bench --bench search
+use std::{collections::HashSet, hint::black_box, ops::Range, time::Instant};
+
+// Only plain byte patterns are compared
+#[allow(dead_code)]
+#[path = "../src/find.rs"]
+mod find;
+
+use find::{find_all, Overlap};
+
+const TEXT_SIZE: usize = 32 << 20;
+
+// Previous implementation, kept as a baseline
+fn naive_search(pattern: &[u8], text: &[u8]) -> HashSet<Range<usize>> {
+    let mut i = 0;
+    let mut j = 0;
+    let mut sequences = HashSet::new();
+
+    loop {
+        if i == pattern.len() {
+            let range = (j + i - 1) - (pattern.len() - 1)..j + i;
+            sequences.insert(range);
+            i = 0;
+            j += 1;
+        }
+
+        if j >= text.len() || i + j >= text.len() {
+            break;
+        }
+
+        let byte_to_match = pattern[i];
+        let byte = &text[j + i];
+
+        if *byte == byte_to_match {
+            i += 1;
+        } else {
+            i = 0;
+            j += 1;
+        }
+    }
+    sequences
+}
+
+// Pseudo random bytes with small alphabet, so that partial matches are common
+fn text() -> Vec<u8> {
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    (0..TEXT_SIZE)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            b"ABCD\0\xff"[(seed % 6) as usize]
+        })
+        .collect()
+}
+
+fn time<T>(run: impl Fn() -> T) -> (T, f64) {
+    let start = Instant::now();
+    let result = black_box(run());
+    (result, start.elapsed().as_secs_f64() * 1000.0)
+}
+
+fn main() {
+    let text = text();
+    let patterns: [&[u8]; 5] = [
+        b"A",
+        b"ABCD",
+        b"\0\0\0\0",
+        b"DCBA\xffABCD",
+        &text[1000..1064],
+    ];
+
+    println!(
+        "{:<10} {:>10} {:>12} {:>12} {:>12}",
+        "pattern", "matches", "naive ms", "skip ms", "overlap ms"
+    );
+
+    for pattern in patterns {
+        let (naive, naive_ms) = time(|| naive_search(pattern, &text));
+        let (overlapping, overlap_ms) = time(|| find_all(pattern, &text, Overlap::Allowed));
+        let (_, skip_ms) = time(|| find_all(pattern, &text, Overlap::Skipped));
+
+        let mut naive: Vec<Range<usize>> = naive.into_iter().collect();
+        naive.sort_by_key(|range| range.start);
+        assert_eq!(naive, overlapping, "Engines disagree");
+
+        println!(
+            "{:<10} {:>10} {:>12.1} {:>12.1} {:>12.1}",
+            format!("{} bytes", pattern.len()),
+            overlapping.len(),
+            naive_ms,
+            skip_ms,
+            overlap_ms
+        );
+    }
+}
@@ -1,28 +1,43 @@
 use std::{
-    fs::{self, OpenOptions},
-    io::{Read, Write},
-    thread::park,
+    fs::{self, File},
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
 };
 
 use crate::{
+    align::Comparison,
+    buffer::{EditBuffer, Origin, PieceTable},
+    follow::scroll_to_end,
     misc::{
-        get_byte_at_cursor, get_column_for_index, get_index_of_closest_change,
-        get_index_of_closest_found, get_offset_for_index, put_cursor_at_index,
+        get_byte_at_cursor, get_index_of_closest_change, get_index_of_closest_difference,
+        get_index_of_closest_found, get_selection_or_cursor, put_cursor_at_index,
+    },
+    misc::{
+        is_changed_on_disk, open_files, switch_tab, Direction, FileStamp, Parameters,
+        DEFAULT_REGISTER,
     },
-    misc::{Direction, Parameters},
     modes::Modes,
-    StatusMode, TermState,
+    search_job, swap, StatusMode, TermState,
 };
 
-pub fn general_status(state: &mut TermState, parameters: &Parameters) -> Modes {
+// Both files are read into memory to be aligned, bigger ones would take too much of it
+// and keep the editor busy for too long
+const COMPARE_LIMIT: usize = 64 << 20;
+
+pub fn general_status(state: &mut TermState, _parameters: &Parameters) -> Modes {
     state.status_mode = StatusMode::General;
     Modes::Bytes
 }
 
-pub fn search(state: &mut TermState, parameters: &Parameters) -> Modes {
+pub fn search(_state: &mut TermState, _parameters: &Parameters) -> Modes {
     Modes::Search
 }
-pub fn help(state: &mut TermState, parameters: &Parameters) -> Modes {
+
+pub fn cancel_search(state: &mut TermState, _parameters: &Parameters) -> Modes {
+    search_job::cancel(state);
+    Modes::Bytes
+}
+pub fn help(state: &mut TermState, _parameters: &Parameters) -> Modes {
     if state.prev_mode != Modes::Help {
         return Modes::Help;
     }
@@ -31,56 +46,256 @@ pub fn help(state: &mut TermState, parameters: &Parameters) -> Modes {
 }
 
 pub fn remove(state: &mut TermState, parameters: &Parameters) -> Modes {
-    let byte_index = get_byte_at_cursor(state, parameters);
-
-    if state.bytes_changed.contains(&byte_index) {
-        state.bytes_changed.remove(&byte_index);
+    if !is_editable(state) {
+        return Modes::Bytes;
     }
 
-    if !state.bytes_removed.contains(&byte_index) {
-        state.bytes_removed.insert(byte_index);
+    let range = get_selection_or_cursor(state, parameters);
+
+    state.buffer.checkpoint(range.start);
+    if state.buffer.is_removed(range.clone()) {
+        state.buffer.restore(range);
     } else {
-        state.bytes_removed.remove(&byte_index);
+        state.buffer.remove(range);
+    }
+    state.selection_anchor = None;
+
+    Modes::Bytes
+}
+
+pub fn select(state: &mut TermState, parameters: &Parameters) -> Modes {
+    state.selection_anchor = match state.selection_anchor {
+        Some(_) => None,
+        None => Some(get_byte_at_cursor(state, parameters)),
+    };
+
+    Modes::Bytes
+}
+
+pub fn select_left(state: &mut TermState, parameters: &Parameters) -> Modes {
+    start_selection(state, parameters);
+    go_left(state, parameters)
+}
+
+pub fn select_right(state: &mut TermState, parameters: &Parameters) -> Modes {
+    start_selection(state, parameters);
+    go_right(state, parameters)
+}
+
+pub fn select_up(state: &mut TermState, parameters: &Parameters) -> Modes {
+    start_selection(state, parameters);
+    go_up(state, parameters)
+}
+
+pub fn select_down(state: &mut TermState, parameters: &Parameters) -> Modes {
+    start_selection(state, parameters);
+    go_down(state, parameters)
+}
+
+pub fn copy(state: &mut TermState, parameters: &Parameters) -> Modes {
+    let range = get_selection_or_cursor(state, parameters);
+    let bytes = state.buffer.read(range);
+    state.registers.insert(state.register, bytes);
+    state.register = DEFAULT_REGISTER;
+    state.selection_anchor = None;
+
+    Modes::Bytes
+}
+
+pub fn paste(state: &mut TermState, parameters: &Parameters) -> Modes {
+    if !is_editable(state) {
+        return Modes::Bytes;
+    }
+
+    paste_register(state, parameters, false);
+    Modes::Bytes
+}
+
+pub fn paste_insert(state: &mut TermState, parameters: &Parameters) -> Modes {
+    if !is_editable(state) {
+        return Modes::Bytes;
+    }
+
+    paste_register(state, parameters, true);
+    Modes::Bytes
+}
+
+pub fn inspector(state: &mut TermState, _parameters: &Parameters) -> Modes {
+    state.inspector = !state.inspector;
+    Modes::Bytes
+}
+
+pub fn inspect(state: &mut TermState, _parameters: &Parameters) -> Modes {
+    if !is_editable(state) {
+        return Modes::Bytes;
+    }
+
+    state.inspector = true;
+    Modes::Inspector
+}
+
+pub fn register(_state: &mut TermState, _parameters: &Parameters) -> Modes {
+    Modes::Register
+}
+
+pub fn export(_state: &mut TermState, _parameters: &Parameters) -> Modes {
+    Modes::Export
+}
+
+pub fn undo(state: &mut TermState, parameters: &Parameters) -> Modes {
+    if !is_editable(state) {
+        return Modes::Bytes;
+    }
+
+    if let Some(byte_index) = state.buffer.undo() {
+        put_cursor_at_index(state, byte_index, parameters);
+    }
+
+    Modes::Bytes
+}
+
+pub fn redo(state: &mut TermState, parameters: &Parameters) -> Modes {
+    if !is_editable(state) {
+        return Modes::Bytes;
+    }
+
+    if let Some(byte_index) = state.buffer.redo() {
+        put_cursor_at_index(state, byte_index, parameters);
     }
 
     Modes::Bytes
 }
 
 pub fn save(state: &mut TermState, parameters: &Parameters) -> Modes {
-    //@Improve: Change this to some sort of Rope data structure in the future.
-    let mut bytes_copy = Vec::with_capacity(state.bytes.len());
-    for i in 0..state.bytes.len() {
-        if state.bytes_removed.contains(&i) {
-            continue;
-        }
-        bytes_copy.push(state.bytes[i]);
+    if !is_editable(state) {
+        return Modes::Bytes;
     }
-    state.bytes = bytes_copy;
-    state.bytes_removed.clear();
 
-    let mut file = OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .open(&parameters.file_path)
-        .expect("Failed to save changes");
+    // Someone else wrote to the file since we loaded it
+    if is_changed_on_disk(state) {
+        return Modes::External;
+    }
 
-    file.write(&state.bytes).expect("Failed to save changes");
-    state.bytes_changed.clear();
+    state.status_mode = match save_buffer(state, parameters) {
+        Ok(()) => StatusMode::General,
+        Err(error) => StatusMode::Error(format!("Failed to save: {}", error)),
+    };
 
     Modes::Bytes
 }
 
-pub fn edit(state: &mut TermState, parameters: &Parameters) -> Modes {
+pub fn save_buffer(state: &mut TermState, parameters: &Parameters) -> io::Result<()> {
+    let path = state.file_path.clone();
+    write_buffer(state, &path, parameters.backup)?;
+    open_buffer(state, &path)?;
+    // Journal of edits made before saving must not be offered for recovery
+    let _ = swap::remove_swap(&path);
+    Ok(())
+}
+
+// Writes whole buffer (without removed bytes) to given path.
+// Original file is read from while writing, so it cannot be truncated before we are done.
+// New content goes to a sibling file which then atomically replaces the target,
+// so crash or full disk mid-write leaves the target untouched.
+// Symlinks are followed and the file they point to is replaced. Permissions are kept,
+// but owner is not, and other hard links to the file keep the old content.
+pub fn write_buffer(state: &TermState, path: &str, backup: bool) -> io::Result<()> {
+    // New file can't be resolved yet
+    let target = fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+    let with_extension = |extension: &str| {
+        let mut path = target.clone().into_os_string();
+        path.push(extension);
+        PathBuf::from(path)
+    };
+    let temp_path = with_extension(".hex-rs");
+
+    if let Err(error) = write_temp(state, &target, &temp_path) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(error);
+    }
+
+    if backup && target.exists() {
+        fs::copy(&target, with_extension(".bak"))?;
+    }
+
+    if let Err(error) = fs::rename(&temp_path, &target) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(error);
+    }
+
+    Ok(())
+}
+
+// Replaces buffer with content of given file, history is lost
+pub fn open_buffer(state: &mut TermState, path: &str) -> io::Result<()> {
+    let file = File::open(path)?;
+    let origin = Origin::open(file)?;
+    state.buffer = PieceTable::new(origin);
+    state.file_path = path.to_string();
+    state.stamp = FileStamp::of(path);
+
+    Ok(())
+}
+
+fn write_temp(state: &TermState, path: &Path, temp_path: &Path) -> io::Result<()> {
+    // Existing file keeps its permissions, new one gets the default ones
+    let permissions = fs::metadata(path)
+        .ok()
+        .map(|metadata| metadata.permissions());
+
+    let file = File::create(temp_path)?;
+    let mut writer = BufWriter::new(file);
+    state.buffer.write_to(&mut writer)?;
+
+    let file = writer.into_inner().map_err(|error| error.into_error())?;
+    file.sync_all()?;
+    if let Some(permissions) = permissions {
+        file.set_permissions(permissions)?;
+    }
+
+    Ok(())
+}
+
+pub fn save_as(state: &mut TermState, _parameters: &Parameters) -> Modes {
+    if !is_editable(state) {
+        return Modes::Bytes;
+    }
+
+    Modes::SaveAs
+}
+
+pub fn edit(state: &mut TermState, _parameters: &Parameters) -> Modes {
+    if !is_editable(state) {
+        return Modes::Bytes;
+    }
+
     Modes::Change
 }
 
-pub fn go_to_start(state: &mut TermState, parameters: &Parameters) -> Modes {
+pub fn insert(state: &mut TermState, _parameters: &Parameters) -> Modes {
+    if !is_editable(state) {
+        return Modes::Bytes;
+    }
+
+    Modes::Insert
+}
+
+pub fn follow(state: &mut TermState, parameters: &Parameters) -> Modes {
+    state.follow = !state.follow;
+    if state.follow {
+        scroll_to_end(state, parameters);
+    }
+
+    Modes::Bytes
+}
+
+pub fn go_to_start(state: &mut TermState, _parameters: &Parameters) -> Modes {
     state.render_from_offset = 0;
 
     Modes::Bytes
 }
 pub fn go_to_end(state: &mut TermState, parameters: &Parameters) -> Modes {
-    let last_offset = state.bytes.len() / parameters.byte_size as usize;
+    let last_offset = state.buffer.len() / parameters.byte_size as usize;
 
     // @Improvement: take '2' from parameters or state.
     let number_of_rows_visible = state.term_height - 2;
@@ -88,30 +303,30 @@ pub fn go_to_end(state: &mut TermState, parameters: &Parameters) -> Modes {
     state.render_from_offset = last_offset - number_of_rows_visible as usize;
     Modes::Bytes
 }
-pub fn go_left(state: &mut TermState, parameters: &Parameters) -> Modes {
-    let jump_by = calculate_leap(&state, Direction::Left);
+pub fn go_left(state: &mut TermState, _parameters: &Parameters) -> Modes {
+    let jump_by = calculate_leap(state, Direction::Left);
     if jump_by <= state.column {
         state.column -= jump_by;
     }
     Modes::Bytes
 }
 
-pub fn go_right(state: &mut TermState, parameters: &Parameters) -> Modes {
-    let jump_by = calculate_leap(&state, Direction::Right);
+pub fn go_right(state: &mut TermState, _parameters: &Parameters) -> Modes {
+    let jump_by = calculate_leap(state, Direction::Right);
     if state.column + jump_by <= state.term_width {
         state.column += jump_by;
     }
     Modes::Bytes
 }
 
-pub fn go_up(state: &mut TermState, parameters: &Parameters) -> Modes {
+pub fn go_up(state: &mut TermState, _parameters: &Parameters) -> Modes {
     if state.row >= 2 {
         state.row -= 1;
     }
     Modes::Bytes
 }
 
-pub fn go_down(state: &mut TermState, parameters: &Parameters) -> Modes {
+pub fn go_down(state: &mut TermState, _parameters: &Parameters) -> Modes {
     if state.row != state.term_height {
         state.row += 1;
     }
@@ -121,7 +336,7 @@ pub fn go_down(state: &mut TermState, parameters: &Parameters) -> Modes {
 pub fn next_found(state: &mut TermState, parameters: &Parameters) -> Modes {
     let current_byte_index = get_byte_at_cursor(state, parameters);
     let closest_byte_index =
-        get_index_of_closest_found(current_byte_index, &state, Direction::Right);
+        get_index_of_closest_found(current_byte_index, state, Direction::Right);
 
     if closest_byte_index != usize::MAX {
         put_cursor_at_index(state, closest_byte_index, parameters);
@@ -131,8 +346,7 @@ pub fn next_found(state: &mut TermState, parameters: &Parameters) -> Modes {
 
 pub fn prev_found(state: &mut TermState, parameters: &Parameters) -> Modes {
     let current_byte_index = get_byte_at_cursor(state, parameters);
-    let closest_byte_index =
-        get_index_of_closest_found(current_byte_index, &state, Direction::Left);
+    let closest_byte_index = get_index_of_closest_found(current_byte_index, state, Direction::Left);
 
     if closest_byte_index != usize::MAX {
         put_cursor_at_index(state, closest_byte_index, parameters);
@@ -143,7 +357,7 @@ pub fn prev_found(state: &mut TermState, parameters: &Parameters) -> Modes {
 pub fn next_change(state: &mut TermState, parameters: &Parameters) -> Modes {
     let current_byte_index = get_byte_at_cursor(state, parameters);
     let closest_byte_index =
-        get_index_of_closest_change(current_byte_index, &state, Direction::Right);
+        get_index_of_closest_change(current_byte_index, state, Direction::Right);
 
     if closest_byte_index != usize::MAX {
         put_cursor_at_index(state, closest_byte_index, parameters);
@@ -155,7 +369,7 @@ pub fn next_change(state: &mut TermState, parameters: &Parameters) -> Modes {
 pub fn prev_change(state: &mut TermState, parameters: &Parameters) -> Modes {
     let current_byte_index = get_byte_at_cursor(state, parameters);
     let closest_byte_index =
-        get_index_of_closest_change(current_byte_index, &state, Direction::Left);
+        get_index_of_closest_change(current_byte_index, state, Direction::Left);
 
     if closest_byte_index != usize::MAX {
         put_cursor_at_index(state, closest_byte_index, parameters);
@@ -164,20 +378,188 @@ pub fn prev_change(state: &mut TermState, parameters: &Parameters) -> Modes {
     Modes::Bytes
 }
 
-pub fn scroll_up(state: &mut TermState, parameters: &Parameters) -> Modes {
+// Compares active file with the next open one
+pub fn diff(state: &mut TermState, parameters: &Parameters) -> Modes {
+    if state.diff_with.is_some() {
+        state.diff_with = None;
+        return Modes::Bytes;
+    }
+
+    if state.tabs.len() < 2 {
+        state.status_mode = StatusMode::Error(String::from("Open two files to compare them"));
+        return Modes::Bytes;
+    }
+
+    state.diff_with = Some((state.active_tab + 1) % state.tabs.len());
+
+    // Differences are still highlighted in the active file
+    let needed = state.dimensions.decoded.1 + 3 + parameters.byte_size * 6 + 2;
+    if state.term_width < needed {
+        state.status_mode = StatusMode::Error(format!(
+            "Window needs {} columns to show compared file",
+            needed
+        ));
+    }
+
+    Modes::Bytes
+}
+
+// Aligns active file with the compared (or next open) one, so blocks inserted
+// into one of them don't shift the rest
+pub fn compare(state: &mut TermState, parameters: &Parameters) -> Modes {
+    if state.tabs.len() < 2 {
+        state.status_mode = StatusMode::Error(String::from("Open two files to compare them"));
+        return Modes::Bytes;
+    }
+
+    let with = state
+        .diff_with
+        .unwrap_or((state.active_tab + 1) % state.tabs.len());
+    let other = &state.tabs[with].buffer;
+
+    if state.buffer.len() > COMPARE_LIMIT || other.len() > COMPARE_LIMIT {
+        state.status_mode = StatusMode::Error(format!(
+            "Only files up to {} MiB can be compared, use diff for bigger ones",
+            COMPARE_LIMIT >> 20
+        ));
+        return Modes::Bytes;
+    }
+
+    let left = state.buffer.read(0..state.buffer.len());
+    let right = other.read(0..other.len());
+    state.comparison = Some(Comparison::new(
+        &left,
+        &right,
+        with,
+        parameters.byte_size as usize,
+    ));
+
+    Modes::Compare
+}
+
+pub fn next_diff(state: &mut TermState, parameters: &Parameters) -> Modes {
+    go_to_difference(state, parameters, Direction::Right)
+}
+
+pub fn prev_diff(state: &mut TermState, parameters: &Parameters) -> Modes {
+    go_to_difference(state, parameters, Direction::Left)
+}
+
+fn go_to_difference(state: &mut TermState, parameters: &Parameters, direction: Direction) -> Modes {
+    if state.diff_with.is_none() {
+        state.status_mode = StatusMode::Error(String::from("Not comparing files"));
+        return Modes::Bytes;
+    }
+
+    let current_byte_index = get_byte_at_cursor(state, parameters);
+    let closest_byte_index = get_index_of_closest_difference(current_byte_index, state, direction);
+
+    if closest_byte_index != usize::MAX {
+        // Bytes only the compared file has are shown past the end of the active one
+        let last = state.buffer.len().saturating_sub(1);
+        put_cursor_at_index(state, closest_byte_index.min(last), parameters);
+    }
+
+    Modes::Bytes
+}
+
+pub fn scroll_up(state: &mut TermState, _parameters: &Parameters) -> Modes {
     if state.render_from_offset != 0 {
         state.render_from_offset -= 1
     }
     Modes::Bytes
 }
 
-pub fn scroll_down(state: &mut TermState, parameters: &Parameters) -> Modes {
+pub fn scroll_down(state: &mut TermState, _parameters: &Parameters) -> Modes {
     state.render_from_offset += 1;
     Modes::Bytes
 }
 
-pub fn quit(state: &mut TermState, parameters: &Parameters) -> Modes {
-    Modes::Quit
+pub fn next_tab(state: &mut TermState, _parameters: &Parameters) -> Modes {
+    let to = (state.active_tab + 1) % state.tabs.len();
+    activate_tab(state, to)
+}
+
+pub fn prev_tab(state: &mut TermState, _parameters: &Parameters) -> Modes {
+    let to = (state.active_tab + state.tabs.len() - 1) % state.tabs.len();
+    activate_tab(state, to)
+}
+
+// File shown again gets the same checks as when it is opened or focused
+fn activate_tab(state: &mut TermState, to: usize) -> Modes {
+    switch_tab(state, to);
+
+    if !state.follow && is_changed_on_disk(state) {
+        return Modes::External;
+    }
+
+    if !state.readonly && !state.buffer.is_modified() && swap::is_recoverable(state) {
+        return Modes::Recover;
+    }
+
+    Modes::Bytes
+}
+
+pub fn quit(state: &mut TermState, _parameters: &Parameters) -> Modes {
+    confirm_unsaved(state, Modes::Quit)
+}
+
+// Anything closing open files (quitting, switching files) should go through here,
+// so unsaved changes are not lost without asking
+pub fn confirm_unsaved(state: &mut TermState, then: Modes) -> Modes {
+    let modified = open_files(state)
+        .iter()
+        .any(|(_, buffer)| buffer.is_modified());
+    if !modified {
+        return then;
+    }
+
+    state.after_confirm = then;
+    Modes::Confirm
+}
+
+// Edits are refused in read-only mode with a note in status bar
+pub fn is_editable(state: &mut TermState) -> bool {
+    if state.readonly {
+        state.status_mode = StatusMode::Error(String::from("File is opened read-only"));
+    }
+    !state.readonly
+}
+
+fn paste_register(state: &mut TermState, parameters: &Parameters, insert: bool) {
+    let bytes = state
+        .registers
+        .get(&state.register)
+        .cloned()
+        .unwrap_or_default();
+    state.register = DEFAULT_REGISTER;
+
+    paste_bytes(state, parameters, &bytes, insert);
+}
+
+// Pastes at cursor (or start of selection) as a single undo step
+pub fn paste_bytes(state: &mut TermState, parameters: &Parameters, bytes: &[u8], insert: bool) {
+    if bytes.is_empty() {
+        return;
+    }
+
+    let at = get_selection_or_cursor(state, parameters)
+        .start
+        .min(state.buffer.len());
+
+    state.buffer.checkpoint(at);
+    if insert {
+        state.buffer.insert(at, bytes);
+    } else {
+        state.buffer.overwrite(at, bytes);
+    }
+    state.selection_anchor = None;
+}
+
+fn start_selection(state: &mut TermState, parameters: &Parameters) {
+    if state.selection_anchor.is_none() {
+        state.selection_anchor = Some(get_byte_at_cursor(state, parameters));
+    }
 }
 
 fn calculate_leap(state: &TermState, direction: Direction) -> u16 {
@@ -203,5 +585,10 @@ fn calculate_leap(state: &TermState, direction: Direction) -> u16 {
         return 5;
     }
 
+    //Do not allow jump past last decoded char
+    if direction == Direction::Right && state.column + 1 >= dimensions.decoded.1 {
+        return 0;
+    }
+
     1
 }
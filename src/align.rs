@@ -0,0 +1,543 @@
+use std::{collections::HashMap, ops::Range};
+
+use crate::misc::Direction;
+
+// Length of blocks looked up in the other file, shorter matches are not used as anchors
+const BLOCK: usize = 32;
+// Gaps between anchors up to this size are aligned byte by byte
+const MAX_REFINED: usize = 256;
+const BASE: u64 = 257;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HunkKind {
+    Same,
+    Changed,
+    // Only in the right file
+    Inserted,
+    // Only in the left file
+    Deleted,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Hunk {
+    pub kind: HunkKind,
+    pub left: Range<usize>,
+    pub right: Range<usize>,
+}
+
+impl Hunk {
+    // Positions taken in aligned view, shorter side is padded with a gap
+    pub fn width(&self) -> usize {
+        self.left.len().max(self.right.len())
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Match {
+    left: usize,
+    right: usize,
+    len: usize,
+}
+
+// Consecutive hunks starting on a new row of aligned view
+struct Segment {
+    hunks: Range<usize>,
+    row: usize,
+}
+
+// Positions shown in one row of aligned view
+pub struct Row {
+    pub left: Range<usize>,
+    pub right: Range<usize>,
+    // One per position, side with fewer bytes is padded with a gap
+    pub kinds: Vec<HunkKind>,
+}
+
+// Two files split into regions. Bytes changed in place stay in rows of the same bytes around
+// them, so that sparse changes don't break rows apart. Inserted and deleted blocks start
+// on a new row, as does everything after them.
+pub struct Comparison {
+    pub hunks: Vec<Hunk>,
+    // Index of tab compared with the active one
+    pub with: usize,
+    byte_size: usize,
+    segments: Vec<Segment>,
+    // Row each hunk starts in
+    hunk_rows: Vec<usize>,
+    rows: usize,
+}
+
+impl Comparison {
+    pub fn new(left: &[u8], right: &[u8], with: usize, byte_size: usize) -> Self {
+        let hunks = align(left, right);
+        let in_place = |hunk: &Hunk| matches!(hunk.kind, HunkKind::Same | HunkKind::Changed);
+
+        let mut segments: Vec<Segment> = vec![];
+        let mut hunk_rows = Vec::with_capacity(hunks.len());
+        let mut rows = 0;
+        // Positions taken by the last segment so far
+        let mut width: usize = 0;
+        for (i, hunk) in hunks.iter().enumerate() {
+            let joined = in_place(hunk)
+                && segments
+                    .last()
+                    .is_some_and(|segment| in_place(&hunks[segment.hunks.start]));
+
+            if !joined {
+                rows += width.div_ceil(byte_size);
+                width = 0;
+                segments.push(Segment {
+                    hunks: i..i,
+                    row: rows,
+                });
+            }
+
+            hunk_rows.push(rows + width / byte_size);
+            width += hunk.width();
+            if let Some(segment) = segments.last_mut() {
+                segment.hunks.end = i + 1;
+            }
+        }
+        rows += width.div_ceil(byte_size);
+
+        Self {
+            hunks,
+            with,
+            byte_size,
+            segments,
+            hunk_rows,
+            rows,
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn at_row(&self, row: usize) -> Option<Row> {
+        if row >= self.rows {
+            return None;
+        }
+
+        let i = self.segments.partition_point(|segment| segment.row <= row) - 1;
+        let segment = &self.segments[i];
+        let hunks = &self.hunks[segment.hunks.clone()];
+        let (first, last) = (&hunks[0], &hunks[hunks.len() - 1]);
+
+        let width = (last.left.end - first.left.start).max(last.right.end - first.right.start);
+        let from = (row - segment.row) * self.byte_size;
+        let to = (from + self.byte_size).min(width);
+        let side = |start: usize, end: usize| (start + from).min(end)..(start + to).min(end);
+
+        // Hunks of one segment are in place, so position is the same on both sides
+        let kinds = (from..to)
+            .map(|position| {
+                let at = first.left.start + position;
+                let i = hunks.partition_point(|hunk| hunk.left.end <= at);
+                hunks[i.min(hunks.len() - 1)].kind
+            })
+            .collect();
+
+        Some(Row {
+            left: side(first.left.start, last.left.end),
+            right: side(first.right.start, last.right.end),
+            kinds,
+        })
+    }
+
+    // Row of the closest hunk which is not the same in both files
+    pub fn closest_difference(&self, row: usize, direction: Direction) -> Option<usize> {
+        let differs = |i: &usize| self.hunks[*i].kind != HunkKind::Same;
+
+        let found = match direction {
+            Direction::Right => {
+                let next = self.hunk_rows.partition_point(|start| *start <= row);
+                (next..self.hunks.len()).find(differs)
+            }
+            Direction::Left => {
+                let current = self.hunk_rows.partition_point(|start| *start < row);
+                (0..current).rev().find(differs)
+            }
+        };
+
+        found.map(|i| self.hunk_rows[i])
+    }
+
+    // Bytes in hunks of given kind, counted on the side they are in
+    pub fn count(&self, kind: HunkKind) -> usize {
+        self.hunks
+            .iter()
+            .filter(|hunk| hunk.kind == kind)
+            .map(|hunk| hunk.width())
+            .sum()
+    }
+}
+
+// Common regions are anchored with blocks of the left file found in the right one,
+// what lies between them is inserted, deleted or changed
+pub fn align(left: &[u8], right: &[u8]) -> Vec<Hunk> {
+    let prefix = common_prefix(left, right);
+    let suffix = common_suffix(&left[prefix..], &right[prefix..]);
+    let (left_end, right_end) = (left.len() - suffix, right.len() - suffix);
+
+    let mut hunks = vec![];
+    push(&mut hunks, HunkKind::Same, 0..prefix, 0..prefix);
+
+    let (mut l, mut r) = (prefix, prefix);
+    for anchor in anchors(&left[prefix..left_end], &right[prefix..right_end]) {
+        let (anchor_l, anchor_r) = (prefix + anchor.left, prefix + anchor.right);
+        fill_gap(&mut hunks, left, right, l..anchor_l, r..anchor_r);
+
+        l = anchor_l + anchor.len;
+        r = anchor_r + anchor.len;
+        push(&mut hunks, HunkKind::Same, anchor_l..l, anchor_r..r);
+    }
+    fill_gap(&mut hunks, left, right, l..left_end, r..right_end);
+
+    push(
+        &mut hunks,
+        HunkKind::Same,
+        left_end..left.len(),
+        right_end..right.len(),
+    );
+
+    hunks
+}
+
+fn push(hunks: &mut Vec<Hunk>, kind: HunkKind, left: Range<usize>, right: Range<usize>) {
+    if left.is_empty() && right.is_empty() {
+        return;
+    }
+
+    if let Some(last) = hunks.last_mut() {
+        if last.kind == kind {
+            last.left.end = left.end;
+            last.right.end = right.end;
+            return;
+        }
+    }
+
+    hunks.push(Hunk { kind, left, right });
+}
+
+fn common_prefix(left: &[u8], right: &[u8]) -> usize {
+    left.iter().zip(right).take_while(|(l, r)| l == r).count()
+}
+
+fn common_suffix(left: &[u8], right: &[u8]) -> usize {
+    left.iter()
+        .rev()
+        .zip(right.iter().rev())
+        .take_while(|(l, r)| l == r)
+        .count()
+}
+
+fn hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |hash, byte| {
+        hash.wrapping_mul(BASE).wrapping_add(*byte as u64)
+    })
+}
+
+fn anchors(left: &[u8], right: &[u8]) -> Vec<Match> {
+    if left.len() < BLOCK || right.len() < BLOCK {
+        return vec![];
+    }
+
+    // Blocks repeated in left file (padding, tables) can't tell where they belong
+    let mut blocks: HashMap<u64, Option<usize>> = HashMap::new();
+    for start in (0..=left.len() - BLOCK).step_by(BLOCK) {
+        blocks
+            .entry(hash(&left[start..start + BLOCK]))
+            .and_modify(|block| *block = None)
+            .or_insert(Some(start));
+    }
+
+    let out_factor = BASE.wrapping_pow(BLOCK as u32 - 1);
+    let mut matches: Vec<Match> = vec![];
+    let mut r = 0;
+    let mut rolling = hash(&right[..BLOCK]);
+
+    while r + BLOCK <= right.len() {
+        if let Some(Some(l)) = blocks.get(&rolling).copied() {
+            if left[l..l + BLOCK] == right[r..r + BLOCK] {
+                // Match is grown both ways, but not into the previous one
+                let floor = matches.last().map_or(0, |m| m.right + m.len);
+                let back = common_suffix(&left[..l], &right[floor..r]);
+                let forward = common_prefix(&left[l + BLOCK..], &right[r + BLOCK..]);
+
+                matches.push(Match {
+                    left: l - back,
+                    right: r - back,
+                    len: back + BLOCK + forward,
+                });
+
+                r += BLOCK + forward;
+                if r + BLOCK <= right.len() {
+                    rolling = hash(&right[r..r + BLOCK]);
+                }
+                continue;
+            }
+        }
+
+        if r + BLOCK < right.len() {
+            rolling = rolling
+                .wrapping_sub((right[r] as u64).wrapping_mul(out_factor))
+                .wrapping_mul(BASE)
+                .wrapping_add(right[r + BLOCK] as u64);
+        }
+        r += 1;
+    }
+
+    increasing(matches)
+}
+
+// Longest chain of matches in the same order in both files, the rest would cross it
+fn increasing(matches: Vec<Match>) -> Vec<Match> {
+    // Last match of the best chain of each length, chains ending earlier in left first
+    let mut tails: Vec<usize> = vec![];
+    let mut previous: Vec<Option<usize>> = vec![None; matches.len()];
+
+    for (i, m) in matches.iter().enumerate() {
+        let len = tails.partition_point(|tail| matches[*tail].left < m.left);
+        previous[i] = len.checked_sub(1).map(|len| tails[len]);
+        match len == tails.len() {
+            true => tails.push(i),
+            false => tails[len] = i,
+        }
+    }
+
+    let mut chain = vec![];
+    let mut next = tails.last().copied();
+    while let Some(i) = next {
+        chain.push(i);
+        next = previous[i];
+    }
+    chain.reverse();
+
+    // Matches grown backwards may overlap the previous one in left file
+    let mut result: Vec<Match> = vec![];
+    for i in chain {
+        let mut m = matches[i];
+        if let Some(last) = result.last() {
+            let overlap = (last.left + last.len).saturating_sub(m.left);
+            if overlap >= m.len {
+                continue;
+            }
+            m.left += overlap;
+            m.right += overlap;
+            m.len -= overlap;
+        }
+        result.push(m);
+    }
+
+    result
+}
+
+fn fill_gap(hunks: &mut Vec<Hunk>, left: &[u8], right: &[u8], l: Range<usize>, r: Range<usize>) {
+    // Same size means bytes were overwritten in place
+    if l.len() == r.len() {
+        for (i, j) in l.zip(r) {
+            let kind = match left[i] == right[j] {
+                true => HunkKind::Same,
+                false => HunkKind::Changed,
+            };
+            push(hunks, kind, i..i + 1, j..j + 1);
+        }
+        return;
+    }
+
+    match l.is_empty() || r.is_empty() || l.len() > MAX_REFINED || r.len() > MAX_REFINED {
+        true => split(hunks, l, r),
+        false => refine(hunks, left, right, l, r),
+    }
+}
+
+// Part present in both files is changed, the rest inserted or deleted
+fn split(hunks: &mut Vec<Hunk>, l: Range<usize>, r: Range<usize>) {
+    let common = l.len().min(r.len());
+    let (l_common, r_common) = (l.start + common, r.start + common);
+
+    push(
+        hunks,
+        HunkKind::Changed,
+        l.start..l_common,
+        r.start..r_common,
+    );
+    push(hunks, HunkKind::Deleted, l_common..l.end, r.end..r.end);
+    push(hunks, HunkKind::Inserted, l.end..l.end, r_common..r.end);
+}
+
+// Small gaps are aligned through their longest common subsequence
+fn refine(hunks: &mut Vec<Hunk>, left: &[u8], right: &[u8], l: Range<usize>, r: Range<usize>) {
+    let (a, b) = (&left[l.clone()], &right[r.clone()]);
+    let width = b.len() + 1;
+
+    let mut lcs = vec![0u16; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i * width + j] = match a[i] == b[j] {
+                true => lcs[(i + 1) * width + j + 1] + 1,
+                false => lcs[(i + 1) * width + j].max(lcs[i * width + j + 1]),
+            };
+        }
+    }
+
+    // Bytes between two common ones form a single region
+    let (mut i, mut j) = (0, 0);
+    let (mut gap_i, mut gap_j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            split(
+                hunks,
+                l.start + gap_i..l.start + i,
+                r.start + gap_j..r.start + j,
+            );
+            push(
+                hunks,
+                HunkKind::Same,
+                l.start + i..l.start + i + 1,
+                r.start + j..r.start + j + 1,
+            );
+            i += 1;
+            j += 1;
+            (gap_i, gap_j) = (i, j);
+        } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    split(hunks, l.start + gap_i..l.end, r.start + gap_j..r.end);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Random;
+
+    // Hunks follow each other on both sides, cover both files and say truth about them
+    fn assert_covers(hunks: &[Hunk], left: &[u8], right: &[u8]) {
+        let (mut l, mut r) = (0, 0);
+        for hunk in hunks {
+            assert_eq!((hunk.left.start, hunk.right.start), (l, r), "{:?}", hunk);
+            assert!(hunk.width() > 0);
+            (l, r) = (hunk.left.end, hunk.right.end);
+
+            match hunk.kind {
+                HunkKind::Same => assert_eq!(left[hunk.left.clone()], right[hunk.right.clone()]),
+                HunkKind::Changed => assert_eq!(hunk.left.len(), hunk.right.len()),
+                HunkKind::Inserted => assert!(hunk.left.is_empty()),
+                HunkKind::Deleted => assert!(hunk.right.is_empty()),
+            }
+        }
+        assert_eq!((l, r), (left.len(), right.len()));
+    }
+
+    #[test]
+    fn hunks_cover_both_files() {
+        for seed in 1..30 {
+            let mut random = Random(seed);
+            let len = random.below(4096);
+            let left = random.bytes(len);
+            let mut right = left.clone();
+
+            for _ in 0..random.below(6) {
+                let at = random.below(right.len() + 1);
+                let len = random.below(300);
+                match random.below(3) {
+                    0 => {
+                        let inserted = random.bytes(len);
+                        right.splice(at..at, inserted);
+                    }
+                    1 => {
+                        right.drain(at..(at + len).min(right.len()));
+                    }
+                    _ => {
+                        let end = (at + len).min(right.len());
+                        let changed = random.bytes(end - at);
+                        right.splice(at..end, changed);
+                    }
+                }
+            }
+
+            assert_covers(&align(&left, &right), &left, &right);
+            assert_covers(&align(&right, &left), &right, &left);
+
+            // Rows show every byte once, in order
+            let comparison = Comparison::new(&left, &right, 1, 16);
+            let (mut l, mut r) = (0, 0);
+            for row in (0..comparison.rows()).map(|row| comparison.at_row(row).unwrap()) {
+                assert_eq!((row.left.start, row.right.start), (l, r));
+                assert_eq!(row.kinds.len(), row.left.len().max(row.right.len()));
+                (l, r) = (row.left.end, row.right.end);
+            }
+            assert_eq!((l, r), (left.len(), right.len()));
+        }
+    }
+
+    #[test]
+    fn inserted_block_keeps_rest_aligned() {
+        let mut random = Random(7);
+        let left = random.bytes(10_000);
+        let mut right = left.clone();
+        right.splice(4000..4000, random.bytes(1000));
+
+        let hunks = align(&left, &right);
+        assert_covers(&hunks, &left, &right);
+        assert_eq!(hunks.len(), 3);
+        assert_eq!(hunks[1].kind, HunkKind::Inserted);
+        assert_eq!(hunks[1].right, 4000..5000);
+    }
+
+    #[test]
+    fn rows_of_comparison() {
+        let left = b"0123456789abcdef".to_vec();
+        let mut right = left.clone();
+        right.splice(8..8, *b"XYZ");
+
+        let comparison = Comparison::new(&left, &right, 1, 4);
+        assert_eq!(comparison.count(HunkKind::Inserted), 3);
+        // Same 8 bytes, 3 inserted, same 8 bytes
+        assert_eq!(comparison.rows(), 2 + 1 + 2);
+
+        let row = comparison.at_row(3).unwrap();
+        assert_eq!((row.left, row.right), (8..12, 11..15));
+        assert_eq!(row.kinds, vec![HunkKind::Same; 4]);
+        let row = comparison.at_row(2).unwrap();
+        assert_eq!((row.left, row.right), (8..8, 8..11));
+        assert_eq!(row.kinds, vec![HunkKind::Inserted; 3]);
+        assert!(comparison.at_row(5).is_none());
+
+        assert_eq!(comparison.closest_difference(0, Direction::Right), Some(2));
+        assert_eq!(comparison.closest_difference(4, Direction::Left), Some(2));
+        assert_eq!(comparison.closest_difference(2, Direction::Right), None);
+    }
+
+    #[test]
+    fn changed_bytes_stay_in_their_rows() {
+        let left: Vec<u8> = (0..64).collect();
+        let mut right = left.clone();
+        right[5] = 0xFF;
+        right[40] = 0xFF;
+        right[41] = 0xFF;
+
+        let comparison = Comparison::new(&left, &right, 1, 16);
+        assert_eq!(comparison.count(HunkKind::Changed), 3);
+        assert_eq!(comparison.rows(), 4);
+
+        let row = comparison.at_row(0).unwrap();
+        assert_eq!((row.left, row.right), (0..16, 0..16));
+        let changed: Vec<usize> = (0..16)
+            .filter(|i| row.kinds[*i] == HunkKind::Changed)
+            .collect();
+        assert_eq!(changed, vec![5]);
+
+        let row = comparison.at_row(2).unwrap();
+        assert_eq!((row.left, row.right), (32..48, 32..48));
+        assert_eq!(row.kinds[8..10], [HunkKind::Changed; 2]);
+
+        assert_eq!(comparison.closest_difference(0, Direction::Right), Some(2));
+        assert_eq!(comparison.closest_difference(2, Direction::Left), Some(0));
+        assert_eq!(comparison.closest_difference(2, Direction::Right), None);
+    }
+}
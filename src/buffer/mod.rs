@@ -0,0 +1,73 @@
+use std::{
+    io::{Result, Write},
+    ops::Range,
+};
+
+mod origin;
+mod piece_table;
+
+pub use origin::Origin;
+pub use piece_table::PieceTable;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ByteState {
+    Original,
+    Changed,
+    Inserted,
+    Removed,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Span {
+    pub range: Range<usize>,
+    pub state: ByteState,
+}
+
+pub trait EditBuffer {
+    fn len(&self) -> usize;
+
+    fn read(&self, range: Range<usize>) -> Vec<u8>;
+
+    fn overwrite(&mut self, index: usize, bytes: &[u8]);
+
+    // Shifts everything from index onwards, index equal to len appends.
+    fn insert(&mut self, index: usize, bytes: &[u8]);
+
+    // Removed bytes stay visible (and keep their offsets) until the buffer is saved.
+    fn remove(&mut self, range: Range<usize>);
+    fn restore(&mut self, range: Range<usize>);
+
+    // Every non original span intersecting range, clipped to it and sorted by offset.
+    fn spans(&self, range: Range<usize>) -> Vec<Span>;
+
+    fn count(&self, state: ByteState) -> usize;
+
+    // True when buffer differs from what is on disk
+    fn is_modified(&self) -> bool {
+        self.count(ByteState::Changed) > 0
+            || self.count(ByteState::Inserted) > 0
+            || self.count(ByteState::Removed) > 0
+    }
+
+    fn is_removed(&self, range: Range<usize>) -> bool {
+        let len = range.len();
+        let removed: usize = self
+            .spans(range)
+            .iter()
+            .filter(|span| span.state == ByteState::Removed)
+            .map(|span| span.range.len())
+            .sum();
+
+        len != 0 && removed == len
+    }
+
+    // Marks start of an undo step, every edit made until next checkpoint is undone together.
+    fn checkpoint(&mut self, at: usize);
+
+    // Both return byte index at which undone/redone step was made.
+    fn undo(&mut self) -> Option<usize>;
+    fn redo(&mut self) -> Option<usize>;
+
+    // Writes content without removed bytes.
+    fn write_to(&self, out: &mut dyn Write) -> Result<()>;
+}
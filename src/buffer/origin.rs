@@ -0,0 +1,229 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Error, ErrorKind, Read, Result},
+    ops::Range,
+    sync::Mutex,
+    time::SystemTime,
+};
+
+const PAGE: usize = 64 << 10;
+// Up to 16 MiB of the file is kept around for redraws
+const CACHED_PAGES: usize = 256;
+
+// Bytes the buffer was loaded from. Regular files are read page by page when needed,
+// so only the pages we actually touch (visible window, edits) are resident.
+// They are not memory mapped, reading a mapping of a file truncated by another program
+// kills the process, while reading the file past its new end only fails.
+pub enum Origin {
+    Memory(Vec<u8>),
+    Paged(Paged),
+}
+
+pub struct Paged {
+    file: File,
+    len: usize,
+    modified: Option<SystemTime>,
+    cache: Mutex<Cache>,
+}
+
+// Least recently used pages are dropped first
+#[derive(Default)]
+struct Cache {
+    pages: HashMap<usize, (Vec<u8>, u64)>,
+    tick: u64,
+}
+
+impl Origin {
+    pub fn open(mut file: File) -> Result<Self> {
+        let metadata = file.metadata()?;
+
+        if metadata.is_file() {
+            return Ok(Origin::Paged(Paged {
+                file,
+                len: metadata.len() as usize,
+                modified: metadata.modified().ok(),
+                cache: Mutex::new(Cache::default()),
+            }));
+        }
+
+        // Pipes, devices and others that cannot be read at random offsets
+        let mut bytes = Vec::with_capacity(metadata.len() as usize);
+        file.read_to_end(&mut bytes)?;
+        Ok(Origin::Memory(bytes))
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Origin::Memory(bytes) => bytes.len(),
+            Origin::Paged(paged) => paged.len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Another program wrote to the file after it was opened, bytes not cached yet are new ones.
+    // Handle keeps pointing to the same file, so it is not fooled by renames.
+    pub fn is_rewritten(&self) -> bool {
+        match self {
+            Origin::Memory(_) => false,
+            Origin::Paged(paged) => {
+                let modified = paged
+                    .file
+                    .metadata()
+                    .and_then(|metadata| metadata.modified());
+                modified.ok() != paged.modified
+            }
+        }
+    }
+
+    // Appends bytes in range to out, fails when file got shorter since it was opened
+    pub fn read_into(&self, range: Range<usize>, out: &mut Vec<u8>) -> Result<()> {
+        match self {
+            Origin::Memory(bytes) => out.extend_from_slice(&bytes[range]),
+            Origin::Paged(paged) => paged.read_into(range, out)?,
+        }
+        Ok(())
+    }
+}
+
+impl Paged {
+    fn read_into(&self, range: Range<usize>, out: &mut Vec<u8>) -> Result<()> {
+        // Big reads (search, save) would only push out pages needed for redraw
+        if range.len() >= PAGE {
+            let start = out.len();
+            out.resize(start + range.len(), 0);
+            return read_exact_at(&self.file, &mut out[start..], range.start);
+        }
+
+        let mut cache = self.cache.lock().unwrap_or_else(|error| error.into_inner());
+        let mut offset = range.start;
+        while offset < range.end {
+            let page = offset / PAGE;
+            let bytes = cache.page(page, |bytes| {
+                let page_start = page * PAGE;
+                bytes.resize((self.len - page_start).min(PAGE), 0);
+                read_exact_at(&self.file, bytes, page_start)
+            })?;
+
+            let from = offset - page * PAGE;
+            let to = (range.end - page * PAGE).min(bytes.len());
+            out.extend_from_slice(&bytes[from..to]);
+            offset = page * PAGE + to;
+        }
+
+        Ok(())
+    }
+}
+
+impl Cache {
+    fn page(
+        &mut self,
+        page: usize,
+        load: impl FnOnce(&mut Vec<u8>) -> Result<()>,
+    ) -> Result<&[u8]> {
+        self.tick += 1;
+        let tick = self.tick;
+
+        if !self.pages.contains_key(&page) {
+            let mut bytes = vec![];
+            load(&mut bytes)?;
+
+            if self.pages.len() >= CACHED_PAGES {
+                let oldest = self.pages.iter().min_by_key(|(_, (_, used))| *used);
+                if let Some(oldest) = oldest.map(|(page, _)| *page) {
+                    self.pages.remove(&oldest);
+                }
+            }
+            self.pages.insert(page, (bytes, tick));
+        }
+
+        let (bytes, used) = self.pages.get_mut(&page).unwrap();
+        *used = tick;
+        Ok(bytes)
+    }
+}
+
+fn read_exact_at(file: &File, mut buffer: &mut [u8], mut offset: usize) -> Result<()> {
+    while !buffer.is_empty() {
+        #[cfg(unix)]
+        let read = std::os::unix::fs::FileExt::read_at(file, buffer, offset as u64);
+        #[cfg(windows)]
+        let read = std::os::windows::fs::FileExt::seek_read(file, buffer, offset as u64);
+
+        match read {
+            Ok(0) => {
+                return Err(Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "File was truncated by another program",
+                ))
+            }
+            Ok(read) => {
+                buffer = &mut buffer[read..];
+                offset += read;
+            }
+            Err(error) if error.kind() == ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::{EditBuffer, PieceTable};
+    use std::{env, fs, time::Duration};
+
+    #[test]
+    fn truncated_file_does_not_crash() {
+        let path = env::temp_dir().join(format!("hex-rs-origin-{}", std::process::id()));
+        let content: Vec<u8> = (0..3 * PAGE + 100).map(|i| (i % 251) as u8).collect();
+        fs::write(&path, &content).unwrap();
+
+        let table = PieceTable::new(Origin::open(File::open(&path).unwrap()).unwrap());
+        assert_eq!(table.read(10..20), &content[10..20]);
+        assert_eq!(table.read(PAGE - 4..PAGE + 4), &content[PAGE - 4..PAGE + 4]);
+        let mut written = vec![];
+        table.write_to(&mut written).unwrap();
+        assert_eq!(written, content);
+
+        // Rewritten in place by another program
+        fs::write(&path, b"short").unwrap();
+
+        // Pages read before are kept, the rest can't be read anymore
+        assert_eq!(table.read(10..20), &content[10..20]);
+        assert_eq!(table.read(2 * PAGE..2 * PAGE + 16), vec![0; 16]);
+        assert!(table.write_to(&mut vec![]).is_err());
+        assert!(table.read_original(0..2 * PAGE).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rewritten_file_is_not_saved() {
+        let path = env::temp_dir().join(format!("hex-rs-rewritten-{}", std::process::id()));
+        let content: Vec<u8> = (0..3 * PAGE).map(|i| (i % 251) as u8).collect();
+        fs::write(&path, &content).unwrap();
+
+        let table = PieceTable::new(Origin::open(File::open(&path).unwrap()).unwrap());
+        assert_eq!(table.read(10..20), &content[10..20]);
+
+        // Same size, same file, other bytes
+        let rewritten = vec![0xAA; content.len()];
+        fs::write(&path, &rewritten).unwrap();
+        // Coarse timestamps can't tell apart writes made right after opening
+        let file = File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(1))
+            .unwrap();
+
+        // Cached page is the old one, the rest is read from the new content
+        assert_eq!(table.read(10..20), &content[10..20]);
+        assert_eq!(table.read(2 * PAGE..2 * PAGE + 4), vec![0xAA; 4]);
+        assert!(table.write_to(&mut vec![]).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,722 @@
+use std::{
+    io::{Error, ErrorKind, Read, Result, Write},
+    ops::Range,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use super::{ByteState, EditBuffer, Origin, Span};
+
+// Original bytes are written out in pieces of this size
+const WRITE_CHUNK: usize = 1 << 20;
+
+// Shared by all buffers, so buffer replaced by saving or reloading doesn't repeat revisions
+static REVISION: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Source {
+    Original,
+    Added,
+}
+
+enum History {
+    Undo,
+    Redo,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Piece {
+    source: Source,
+    start: usize,
+    len: usize,
+    removed: bool,
+    inserted: bool,
+}
+
+impl Piece {
+    fn state(&self) -> ByteState {
+        if self.removed {
+            return ByteState::Removed;
+        }
+
+        match (self.source, self.inserted) {
+            (Source::Original, _) => ByteState::Original,
+            (Source::Added, true) => ByteState::Inserted,
+            (Source::Added, false) => ByteState::Changed,
+        }
+    }
+
+    fn continues(&self, other: &Piece) -> bool {
+        self.source == other.source
+            && self.removed == other.removed
+            && self.inserted == other.inserted
+            && self.start + self.len == other.start
+    }
+}
+
+// Pieces as they were before an edit made at given byte index.
+// Added bytes are never overwritten, so old pieces stay valid forever.
+struct Step {
+    pieces: Vec<Piece>,
+    at: usize,
+}
+
+pub struct PieceTable {
+    // Shared with snapshots, which may be read on other threads
+    original: Arc<Origin>,
+    added: Vec<u8>,
+    pieces: Vec<Piece>,
+    // Logical offset at which each piece starts, kept in sync with pieces.
+    starts: Vec<usize>,
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+    // Changed on every edit, so it is cheap to tell whether anything changed
+    revision: usize,
+}
+
+impl PieceTable {
+    pub fn new(original: Origin) -> Self {
+        let mut table = Self {
+            original: Arc::new(original),
+            added: vec![],
+            pieces: vec![],
+            starts: vec![],
+            undo: vec![],
+            redo: vec![],
+            revision: 0,
+        };
+
+        if !table.original.is_empty() {
+            table.pieces.push(Piece {
+                source: Source::Original,
+                start: 0,
+                len: table.original.len(),
+                removed: false,
+                inserted: false,
+            });
+        }
+
+        table.reindex();
+        table
+    }
+
+    // Original file got longer, bytes past its old end are added at the end of buffer.
+    // Pieces kept in history get them too, so undo does not hide them.
+    pub fn grow(&mut self, original: Origin) {
+        let old_len = self.original.len();
+        if original.len() <= old_len {
+            return;
+        }
+
+        let tail = Piece {
+            source: Source::Original,
+            start: old_len,
+            len: original.len() - old_len,
+            removed: false,
+            inserted: false,
+        };
+
+        self.original = Arc::new(original);
+        self.pieces.push(tail);
+        for step in self.undo.iter_mut().chain(self.redo.iter_mut()) {
+            step.pieces.push(tail);
+        }
+        self.reindex();
+    }
+
+    // Current content without history, original bytes are shared rather than copied
+    pub fn snapshot(&self) -> Self {
+        Self {
+            original: Arc::clone(&self.original),
+            added: self.added.clone(),
+            pieces: self.pieces.clone(),
+            starts: self.starts.clone(),
+            undo: vec![],
+            redo: vec![],
+            revision: self.revision,
+        }
+    }
+
+    // Bytes of the file as it is read now, which fails when it got shorter on disk
+    pub fn read_original(&self, range: Range<usize>) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(range.len());
+        self.original.read_into(range, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    pub fn original_len(&self) -> usize {
+        self.original.len()
+    }
+
+    pub fn is_original_rewritten(&self) -> bool {
+        self.original.is_rewritten()
+    }
+
+    pub fn revision(&self) -> usize {
+        self.revision
+    }
+
+    // Journal holds added bytes followed by pieces, which is enough to rebuild
+    // every overwrite, removal and insert on top of the same original file
+    pub fn write_journal(&self, out: &mut dyn Write) -> Result<()> {
+        write_usize(out, self.added.len())?;
+        out.write_all(&self.added)?;
+
+        write_usize(out, self.pieces.len())?;
+        for piece in &self.pieces {
+            let source = match piece.source {
+                Source::Original => 0,
+                Source::Added => 1,
+            };
+            out.write_all(&[source, piece.removed as u8, piece.inserted as u8])?;
+            write_usize(out, piece.start)?;
+            write_usize(out, piece.len)?;
+        }
+
+        out.flush()
+    }
+
+    // Replaces pieces with those from journal, as a single undo step.
+    // Journaled bytes are appended, so pieces kept in history stay valid.
+    pub fn read_journal(&mut self, input: &mut dyn Read) -> Result<()> {
+        let invalid = || Error::new(ErrorKind::InvalidData, "Corrupted journal");
+
+        let added_len = read_usize(input)?;
+        let mut added = vec![];
+        input.take(added_len as u64).read_to_end(&mut added)?;
+        if added.len() != added_len {
+            return Err(invalid());
+        }
+
+        let count = read_usize(input)?;
+        let mut pieces = vec![];
+        for _ in 0..count {
+            let mut flags = [0; 3];
+            input.read_exact(&mut flags)?;
+
+            let (source, source_len) = match flags[0] {
+                0 => (Source::Original, self.original.len()),
+                1 => (Source::Added, added.len()),
+                _ => return Err(invalid()),
+            };
+            let piece = Piece {
+                source,
+                removed: flags[1] != 0,
+                inserted: flags[2] != 0,
+                start: read_usize(input)?,
+                len: read_usize(input)?,
+            };
+
+            match piece.start.checked_add(piece.len) {
+                Some(end) if end <= source_len => pieces.push(piece),
+                _ => return Err(invalid()),
+            }
+        }
+
+        let base = self.added.len();
+        for piece in pieces
+            .iter_mut()
+            .filter(|piece| piece.source == Source::Added)
+        {
+            piece.start += base;
+        }
+
+        self.checkpoint(0);
+        self.added.extend_from_slice(&added);
+        self.pieces = pieces;
+        self.reindex();
+
+        Ok(())
+    }
+
+    // Appends bytes of piece from given position within it
+    fn read_piece(&self, piece: &Piece, within: Range<usize>, out: &mut Vec<u8>) -> Result<()> {
+        let range = piece.start + within.start..piece.start + within.end;
+        match piece.source {
+            Source::Original => self.original.read_into(range, out)?,
+            Source::Added => out.extend_from_slice(&self.added[range]),
+        }
+        Ok(())
+    }
+
+    // Index of piece containing given offset
+    fn find(&self, offset: usize) -> usize {
+        self.starts.partition_point(|start| *start <= offset) - 1
+    }
+
+    // Makes sure a piece starts exactly at offset and returns its index
+    fn split(&mut self, offset: usize) -> usize {
+        if offset >= self.len() {
+            return self.pieces.len();
+        }
+
+        let i = self.find(offset);
+        let left_len = offset - self.starts[i];
+        if left_len == 0 {
+            return i;
+        }
+
+        let piece = self.pieces[i];
+        let right = Piece {
+            start: piece.start + left_len,
+            len: piece.len - left_len,
+            ..piece
+        };
+        self.pieces[i].len = left_len;
+        self.pieces.insert(i + 1, right);
+        self.starts.insert(i + 1, offset);
+
+        i + 1
+    }
+
+    // Splits pieces on both ends of range and returns indexes of pieces covering it
+    fn isolate(&mut self, range: Range<usize>) -> Range<usize> {
+        self.split(range.end);
+        let start = self.split(range.start);
+        let end = match range.end >= self.len() {
+            true => self.pieces.len(),
+            false => self.find(range.end),
+        };
+        start..end
+    }
+
+    fn reindex(&mut self) {
+        self.revision = REVISION.fetch_add(1, Ordering::Relaxed) + 1;
+
+        let mut merged: Vec<Piece> = Vec::with_capacity(self.pieces.len());
+        for piece in self.pieces.drain(..) {
+            if piece.len == 0 {
+                continue;
+            }
+            match merged.last_mut() {
+                Some(last) if last.continues(&piece) => last.len += piece.len,
+                _ => merged.push(piece),
+            }
+        }
+        self.pieces = merged;
+
+        self.starts.clear();
+        let mut offset = 0;
+        for piece in &self.pieces {
+            self.starts.push(offset);
+            offset += piece.len;
+        }
+    }
+
+    // Moves current pieces onto one history stack and restores top of the other
+    fn travel(&mut self, direction: History) -> Option<usize> {
+        let (from, to) = match direction {
+            History::Undo => (&mut self.undo, &mut self.redo),
+            History::Redo => (&mut self.redo, &mut self.undo),
+        };
+
+        let step = from.pop()?;
+        to.push(Step {
+            pieces: std::mem::replace(&mut self.pieces, step.pieces),
+            at: step.at,
+        });
+        self.reindex();
+
+        Some(step.at)
+    }
+
+    fn set_removed(&mut self, range: Range<usize>, removed: bool) {
+        let range = range.start..range.end.min(self.len());
+        if range.is_empty() {
+            return;
+        }
+
+        let pieces = self.isolate(range);
+        for piece in &mut self.pieces[pieces] {
+            piece.removed = removed;
+        }
+        self.reindex();
+    }
+}
+
+impl EditBuffer for PieceTable {
+    fn len(&self) -> usize {
+        match (self.starts.last(), self.pieces.last()) {
+            (Some(start), Some(piece)) => start + piece.len,
+            _ => 0,
+        }
+    }
+
+    fn read(&self, range: Range<usize>) -> Vec<u8> {
+        let end = range.end.min(self.len());
+        let mut bytes = Vec::with_capacity(end.saturating_sub(range.start));
+        if range.start >= end {
+            return bytes;
+        }
+
+        let mut offset = range.start;
+        let mut i = self.find(offset);
+        while offset < end {
+            let piece = &self.pieces[i];
+            let from = offset - self.starts[i];
+            let to = piece.len.min(end - self.starts[i]);
+
+            // Bytes the original file no longer has are shown as zeros
+            let filled = bytes.len();
+            if self.read_piece(piece, from..to, &mut bytes).is_err() {
+                bytes.resize(filled + to - from, 0);
+            }
+
+            offset = self.starts[i] + to;
+            i += 1;
+        }
+
+        bytes
+    }
+
+    fn overwrite(&mut self, index: usize, bytes: &[u8]) {
+        let end = (index + bytes.len()).min(self.len());
+        if index >= end {
+            return;
+        }
+
+        let mut start = self.added.len();
+        self.added.extend_from_slice(&bytes[..end - index]);
+
+        // Overwritten pieces keep telling whether they were inserted
+        let pieces = self.isolate(index..end);
+        for piece in &mut self.pieces[pieces] {
+            *piece = Piece {
+                source: Source::Added,
+                start,
+                removed: false,
+                ..*piece
+            };
+            start += piece.len;
+        }
+        self.reindex();
+    }
+
+    fn insert(&mut self, index: usize, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+
+        let piece = Piece {
+            source: Source::Added,
+            start: self.added.len(),
+            len: bytes.len(),
+            removed: false,
+            inserted: true,
+        };
+        self.added.extend_from_slice(bytes);
+
+        let at = self.split(index.min(self.len()));
+        self.pieces.insert(at, piece);
+        self.reindex();
+    }
+
+    fn remove(&mut self, range: Range<usize>) {
+        self.set_removed(range, true);
+    }
+
+    fn restore(&mut self, range: Range<usize>) {
+        self.set_removed(range, false);
+    }
+
+    fn spans(&self, range: Range<usize>) -> Vec<Span> {
+        let end = range.end.min(self.len());
+        let mut spans = vec![];
+        if range.start >= end {
+            return spans;
+        }
+
+        let first = self.find(range.start);
+        for (i, piece) in self.pieces.iter().enumerate().skip(first) {
+            let start = self.starts[i];
+            if start >= end {
+                break;
+            }
+
+            let state = piece.state();
+            if state == ByteState::Original {
+                continue;
+            }
+
+            spans.push(Span {
+                range: start.max(range.start)..(start + piece.len).min(end),
+                state,
+            });
+        }
+
+        spans
+    }
+
+    fn count(&self, state: ByteState) -> usize {
+        self.pieces
+            .iter()
+            .filter(|piece| piece.state() == state)
+            .map(|piece| piece.len)
+            .sum()
+    }
+
+    fn checkpoint(&mut self, at: usize) {
+        self.undo.push(Step {
+            pieces: self.pieces.clone(),
+            at,
+        });
+        self.redo.clear();
+    }
+
+    fn undo(&mut self) -> Option<usize> {
+        self.travel(History::Undo)
+    }
+
+    fn redo(&mut self) -> Option<usize> {
+        self.travel(History::Redo)
+    }
+
+    fn write_to(&self, out: &mut dyn Write) -> Result<()> {
+        // Would write a mix of old and new bytes of the original file
+        if self.original.is_rewritten() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "File was rewritten by another program, reload it",
+            ));
+        }
+
+        let mut bytes = vec![];
+        for piece in self.pieces.iter().filter(|piece| !piece.removed) {
+            for from in (0..piece.len).step_by(WRITE_CHUNK) {
+                bytes.clear();
+                self.read_piece(piece, from..(from + WRITE_CHUNK).min(piece.len), &mut bytes)?;
+                out.write_all(&bytes)?;
+            }
+        }
+        out.flush()
+    }
+}
+
+fn write_usize(out: &mut dyn Write, value: usize) -> Result<()> {
+    out.write_all(&(value as u64).to_le_bytes())
+}
+
+fn read_usize(input: &mut dyn Read) -> Result<usize> {
+    let mut bytes = [0; 8];
+    input.read_exact(&mut bytes)?;
+    usize::try_from(u64::from_le_bytes(bytes))
+        .map_err(|_| Error::new(ErrorKind::InvalidData, "Corrupted journal"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Random;
+
+    // Byte by byte model of what the buffer should hold
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    struct Byte {
+        value: u8,
+        inserted: bool,
+        changed: bool,
+        removed: bool,
+    }
+
+    #[derive(Clone, Default)]
+    struct Model {
+        bytes: Vec<Byte>,
+        undo: Vec<(Vec<Byte>, usize)>,
+        redo: Vec<(Vec<Byte>, usize)>,
+    }
+
+    impl Model {
+        fn new(original: &[u8]) -> Self {
+            let bytes = original
+                .iter()
+                .map(|value| Byte {
+                    value: *value,
+                    inserted: false,
+                    changed: false,
+                    removed: false,
+                })
+                .collect();
+            Self {
+                bytes,
+                ..Default::default()
+            }
+        }
+
+        fn state(byte: &Byte) -> ByteState {
+            match byte {
+                Byte { removed: true, .. } => ByteState::Removed,
+                Byte { inserted: true, .. } => ByteState::Inserted,
+                Byte { changed: true, .. } => ByteState::Changed,
+                _ => ByteState::Original,
+            }
+        }
+
+        fn travel(&mut self, undo: bool) -> Option<usize> {
+            let (from, to) = match undo {
+                true => (&mut self.undo, &mut self.redo),
+                false => (&mut self.redo, &mut self.undo),
+            };
+            let (bytes, at) = from.pop()?;
+            to.push((std::mem::replace(&mut self.bytes, bytes), at));
+            Some(at)
+        }
+    }
+
+    fn written(table: &PieceTable) -> Vec<u8> {
+        let mut out = vec![];
+        table.write_to(&mut out).unwrap();
+        out
+    }
+
+    fn assert_same(table: &PieceTable, model: &Model) {
+        let values: Vec<u8> = model.bytes.iter().map(|byte| byte.value).collect();
+        let kept: Vec<u8> = model
+            .bytes
+            .iter()
+            .filter(|byte| !byte.removed)
+            .map(|byte| byte.value)
+            .collect();
+
+        assert_eq!(table.len(), values.len());
+        assert_eq!(table.read(0..table.len()), values);
+        assert_eq!(written(table), kept);
+
+        for state in [ByteState::Changed, ByteState::Inserted, ByteState::Removed] {
+            let expected = model
+                .bytes
+                .iter()
+                .filter(|byte| Model::state(byte) == state)
+                .count();
+            assert_eq!(table.count(state), expected, "{:?} bytes", state);
+        }
+    }
+
+    fn edit(table: &mut PieceTable, model: &mut Model, random: &mut Random) {
+        let len = model.bytes.len();
+        let at = random.below(len + 1);
+
+        match random.below(7) {
+            0 => {
+                let count = 1 + random.below(8);
+                let bytes = random.bytes(count);
+                table.insert(at, &bytes);
+                let inserted = bytes.iter().map(|value| Byte {
+                    value: *value,
+                    inserted: true,
+                    changed: false,
+                    removed: false,
+                });
+                model.bytes.splice(at..at, inserted);
+            }
+            1 => {
+                let count = 1 + random.below(8);
+                let bytes = random.bytes(count);
+                table.overwrite(at, &bytes);
+                for (byte, value) in model.bytes[at.min(len)..].iter_mut().zip(&bytes) {
+                    byte.value = *value;
+                    byte.changed = true;
+                    byte.removed = false;
+                }
+            }
+            2 | 3 => {
+                let end = (at + random.below(16)).min(len);
+                let removed = random.below(2) == 0;
+                match removed {
+                    true => table.remove(at..end),
+                    false => table.restore(at..end),
+                }
+                for byte in &mut model.bytes[at.min(len)..end] {
+                    byte.removed = removed;
+                }
+            }
+            4 => {
+                table.checkpoint(at);
+                model.undo.push((model.bytes.clone(), at));
+                model.redo.clear();
+            }
+            5 => assert_eq!(table.undo(), model.travel(true)),
+            _ => assert_eq!(table.redo(), model.travel(false)),
+        }
+    }
+
+    fn table(original: &[u8]) -> PieceTable {
+        PieceTable::new(Origin::Memory(original.to_vec()))
+    }
+
+    #[test]
+    fn edits_match_model() {
+        for seed in 1..20 {
+            let mut random = Random(seed);
+            let original: Vec<u8> = (0..random.below(64)).map(|i| i as u8).collect();
+            let mut table = table(&original);
+            let mut model = Model::new(&original);
+
+            for _ in 0..500 {
+                edit(&mut table, &mut model, &mut random);
+                assert_same(&table, &model);
+            }
+        }
+    }
+
+    #[test]
+    fn spans_cover_edited_bytes() {
+        let mut table = table(b"0123456789");
+        table.overwrite(2, b"ab");
+        table.insert(6, b"xyz");
+        table.remove(10..12);
+
+        let spans = table.spans(0..table.len());
+        let expected = [
+            (2..4, ByteState::Changed),
+            (6..9, ByteState::Inserted),
+            (10..12, ByteState::Removed),
+        ];
+        assert_eq!(spans.len(), expected.len());
+        for (span, (range, state)) in spans.iter().zip(expected) {
+            assert_eq!((span.range.clone(), span.state), (range, state));
+        }
+        assert_eq!(table.spans(3..7).len(), 2);
+    }
+
+    #[test]
+    fn journal_round_trip() {
+        for seed in 1..20 {
+            let mut random = Random(seed);
+            let original: Vec<u8> = (0..random.below(64)).map(|i| (i * 7) as u8).collect();
+            let mut edited = table(&original);
+            let mut model = Model::new(&original);
+            for _ in 0..200 {
+                edit(&mut edited, &mut model, &mut random);
+            }
+
+            let mut journal = vec![];
+            edited.write_journal(&mut journal).unwrap();
+
+            // Recovered edits are a single undo step on top of the original file
+            let mut recovered = table(&original);
+            recovered.read_journal(&mut journal.as_slice()).unwrap();
+            assert_same(&recovered, &model);
+
+            assert_eq!(recovered.undo(), Some(0));
+            assert_same(&recovered, &Model::new(&original));
+        }
+    }
+
+    #[test]
+    fn corrupted_journal_is_rejected() {
+        let mut edited = table(b"0123456789");
+        edited.insert(3, b"abc");
+        let mut journal = vec![];
+        edited.write_journal(&mut journal).unwrap();
+
+        // Pieces reaching past the original file belong to another one
+        let mut shorter = table(b"0123");
+        assert!(shorter.read_journal(&mut journal.as_slice()).is_err());
+
+        let truncated = &journal[..journal.len() - 4];
+        let mut recovered = table(b"0123456789");
+        assert!(recovered.read_journal(&mut &truncated[..]).is_err());
+        assert_eq!(recovered.read(0..recovered.len()), b"0123456789");
+    }
+}
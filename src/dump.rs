@@ -0,0 +1,241 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write},
+};
+
+use crate::misc::{get_symbol, Parameters};
+
+// Prints files the way they are shown in the editor, or as xxd does, for use in scripts
+pub fn dump(parameters: &Parameters) -> io::Result<()> {
+    let mut stdout = BufWriter::new(io::stdout().lock());
+
+    let result = parameters
+        .file_paths
+        .iter()
+        .enumerate()
+        .try_for_each(|(i, file_path)| {
+            if parameters.file_paths.len() > 1 {
+                let separator = if i > 0 { "\n" } else { "" };
+                writeln!(stdout, "{}==> {} <==", separator, file_path)?;
+            }
+            dump_file(&mut stdout, file_path, parameters).map_err(|error| {
+                io::Error::new(error.kind(), format!("'{}': {}", file_path, error))
+            })
+        })
+        .and_then(|_| stdout.flush());
+
+    // Output piped to head and alike is closed early on purpose
+    match result {
+        Err(error) if error.kind() == ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+fn dump_file(stdout: &mut impl Write, file_path: &str, parameters: &Parameters) -> io::Result<()> {
+    let mut file = File::open(file_path)?;
+    file.seek(SeekFrom::Start(parameters.offset as u64))?;
+
+    let limit = parameters.length.map_or(u64::MAX, |length| length as u64);
+    let mut reader = BufReader::new(file).take(limit);
+
+    let columns = parameters.byte_size as usize;
+    let mut row = vec![0; columns];
+    let mut offset = parameters.offset;
+
+    loop {
+        let len = read_row(&mut reader, &mut row)?;
+        if len == 0 {
+            return Ok(());
+        }
+
+        let line = match parameters.xxd {
+            true => xxd_line(offset, &row[..len], columns),
+            false => line(offset, &row[..len], columns),
+        };
+        writeln!(stdout, "{}", line)?;
+
+        offset += len;
+    }
+}
+
+// Fills whole row unless file ends first
+fn read_row(reader: &mut impl Read, row: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < row.len() {
+        match reader.read(&mut row[len..]) {
+            Ok(0) => break,
+            Ok(read) => len += read,
+            Err(error) if error.kind() == ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(len)
+}
+
+// Same columns as bytes screen: offsets, hex values and decoded chars
+fn line(offset: usize, bytes: &[u8], columns: usize) -> String {
+    let hex: Vec<String> = bytes.iter().map(|byte| format!("{:#04X}", byte)).collect();
+    let decoded: String = bytes.iter().map(|byte| get_symbol(*byte)).collect();
+
+    format!(
+        "{:#010x}   {:<width$}   {}",
+        offset,
+        hex.join(" "),
+        decoded,
+        width = columns * 5 - 1
+    )
+}
+
+fn xxd_line(offset: usize, bytes: &[u8], columns: usize) -> String {
+    let mut hex = String::new();
+    for (i, byte) in bytes.iter().enumerate() {
+        if i > 0 && i % 2 == 0 {
+            hex.push(' ');
+        }
+        hex.push_str(&format!("{:02x}", byte));
+    }
+
+    let decoded: String = bytes
+        .iter()
+        .map(|byte| match byte {
+            0x20..=0x7e => char::from(*byte),
+            _ => '.',
+        })
+        .collect();
+
+    format!(
+        "{:08x}: {:<width$}  {}",
+        offset,
+        hex,
+        decoded,
+        width = columns * 2 + (columns - 1) / 2
+    )
+}
+
+// Turns dump printed by hex-rs or xxd back into bytes. Offsets are not used for positioning,
+// so rows can be made longer or shorter when the dump is edited.
+pub fn reverse(parameters: &Parameters) -> io::Result<()> {
+    let dump_path = &parameters.file_paths[0];
+    let reader = BufReader::new(File::open(dump_path)?);
+
+    let mut output: Box<dyn Write> = match parameters.file_paths.get(1) {
+        Some(output_path) => Box::new(BufWriter::new(File::create(output_path)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let bytes = parse_line(&line).map_err(|error| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{}:{}: {}", dump_path, i + 1, error),
+            )
+        })?;
+        output.write_all(&bytes)?;
+    }
+
+    output.flush()
+}
+
+// Hex columns end where decoded ones start, after more than one space
+fn parse_line(line: &str) -> Result<Vec<u8>, String> {
+    let (offset, rest, prefixed) = match line.strip_prefix("0x") {
+        Some(rest) => {
+            let (offset, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+            (offset, rest, true)
+        }
+        None => {
+            let (offset, rest) = line.split_once(':').ok_or("Missing offset")?;
+            (offset, rest, false)
+        }
+    };
+
+    if offset.is_empty() || !offset.chars().all(|char| char.is_ascii_hexdigit()) {
+        return Err(format!("Invalid offset '{}'", offset));
+    }
+
+    let columns = rest.trim_start().split("  ").next().unwrap_or("");
+    let mut bytes = vec![];
+
+    for column in columns.split(' ').filter(|column| !column.is_empty()) {
+        let digits = match prefixed {
+            true => column
+                .strip_prefix("0x")
+                .or(column.strip_prefix("0X"))
+                .ok_or_else(|| format!("Expected 0x prefixed byte, got '{}'", column))?,
+            false => column,
+        };
+
+        if digits.len() % 2 != 0 || !digits.chars().all(|char| char.is_ascii_hexdigit()) {
+            return Err(format!("Invalid hex value '{}'", column));
+        }
+
+        for i in (0..digits.len()).step_by(2) {
+            // Only ASCII digits are left, so slicing by bytes is safe
+            bytes.push(u8::from_str_radix(&digits[i..i + 2], 16).unwrap());
+        }
+    }
+
+    if bytes.is_empty() {
+        return Err(String::from("No bytes in line"));
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Random;
+
+    #[test]
+    fn xxd_lines_match_xxd() {
+        let bytes = b"Hello, world!\n\x00\xff\x7f";
+        assert_eq!(
+            xxd_line(0, &bytes[..16], 16),
+            "00000000: 4865 6c6c 6f2c 2077 6f72 6c64 210a 00ff  Hello, world!..."
+        );
+        assert_eq!(
+            xxd_line(16, &bytes[16..], 16),
+            "00000010: 7f                                       ."
+        );
+        assert_eq!(xxd_line(4, b"o, w", 4), "00000004: 6f2c 2077  o, w");
+    }
+
+    #[test]
+    fn dumped_lines_read_back() {
+        let mut random = Random(3);
+        for columns in [1, 3, 4, 16, 32] {
+            for len in [1, columns / 2 + 1, columns] {
+                let bytes = random.bytes(len);
+                let offset = random.below(1 << 40);
+
+                assert_eq!(
+                    parse_line(&line(offset, &bytes, columns)),
+                    Ok(bytes.clone())
+                );
+                assert_eq!(parse_line(&xxd_line(offset, &bytes, columns)), Ok(bytes));
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_lines_are_explained() {
+        let error = |line: &str| parse_line(line).unwrap_err();
+
+        assert_eq!(error("4865 6c6c"), "Missing offset");
+        assert_eq!(error("0000zz10: 4865"), "Invalid offset '0000zz10'");
+        assert_eq!(error("0x   0x48"), "Invalid offset ''");
+        assert_eq!(
+            error("0x00000000   0x48 65   He"),
+            "Expected 0x prefixed byte, got '65'"
+        );
+        assert_eq!(error("00000000: 486   H"), "Invalid hex value '486'");
+        assert_eq!(error("00000000: 48zz  H"), "Invalid hex value '48zz'");
+        assert_eq!(error("00000000:"), "No bytes in line");
+    }
+}
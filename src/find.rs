@@ -0,0 +1,211 @@
+use std::ops::Range;
+
+use memchr::memmem::Finder;
+use regex::bytes::Regex;
+
+// Regex matches are not found past this many bytes of the next search window,
+// so longer ones may be cut short
+pub const REGEX_MATCH_LIMIT: usize = 4096;
+
+// Whether a match may start inside the previous one, e.g. "AA" in "AAA" is found twice or once
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Overlap {
+    Allowed,
+    Skipped,
+}
+
+// What is searched for, text typed by user is already encoded into bytes
+#[derive(Clone, Debug)]
+pub enum Query {
+    Bytes(Vec<u8>),
+    // ASCII letters match regardless of their case
+    IgnoreCase(Vec<u8>),
+    // Hex pattern with wildcards or alternatives
+    Masked(Vec<MaskedByte>),
+    Regex(Regex),
+}
+
+// Byte matches when it equals one of alternatives in bits set in their masks
+#[derive(Clone, PartialEq, Debug)]
+pub struct MaskedByte {
+    // Value and mask pairs
+    pub alternatives: Vec<(u8, u8)>,
+}
+
+impl MaskedByte {
+    fn matches(&self, byte: u8) -> bool {
+        self.alternatives
+            .iter()
+            .any(|(value, mask)| byte & mask == *value)
+    }
+
+    // Matches every byte
+    pub fn is_any(&self) -> bool {
+        self.alternatives.iter().any(|(_, mask)| *mask == 0)
+    }
+
+    pub fn fixed(&self) -> Option<u8> {
+        match self.alternatives[..] {
+            [(value, 0xFF)] => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl Query {
+    // Longest possible match
+    pub fn len(&self) -> usize {
+        match self {
+            Query::Bytes(pattern) | Query::IgnoreCase(pattern) => pattern.len(),
+            Query::Masked(pattern) => pattern.len(),
+            Query::Regex(_) => REGEX_MATCH_LIMIT,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Bytes on both sides of searched text which assertions like "^", "$" or "\b" look at
+    pub fn context(&self) -> usize {
+        match self {
+            Query::Regex(_) => 1,
+            _ => 0,
+        }
+    }
+
+    // Matches in text starting from given position, bytes before it are only context
+    pub fn find_all(&self, text: &[u8], from: usize, overlap: Overlap) -> Vec<Range<usize>> {
+        let searched = &text[from..];
+        let found = match self {
+            Query::Bytes(pattern) => find_all(pattern, searched, overlap),
+            Query::IgnoreCase(pattern) => find_all(
+                &pattern.to_ascii_lowercase(),
+                &searched.to_ascii_lowercase(),
+                overlap,
+            ),
+            Query::Masked(pattern) => find_masked(pattern, searched, overlap),
+            // Regex matches never overlap, searching from every byte would take too long
+            Query::Regex(regex) => return find_regex(regex, text, from),
+        };
+
+        found
+            .into_iter()
+            .map(|range| range.start + from..range.end + from)
+            .collect()
+    }
+}
+
+// Every occurrence of pattern in text, sorted by position
+pub fn find_all(pattern: &[u8], text: &[u8], overlap: Overlap) -> Vec<Range<usize>> {
+    let mut found = vec![];
+    if pattern.is_empty() {
+        return found;
+    }
+
+    let finder = Finder::new(pattern);
+    let mut from = 0;
+
+    while let Some(i) = finder.find(&text[from..]) {
+        let start = from + i;
+        found.push(start..start + pattern.len());
+
+        from = match overlap {
+            Overlap::Allowed => start + 1,
+            Overlap::Skipped => start + pattern.len(),
+        };
+        if from >= text.len() {
+            break;
+        }
+    }
+
+    found
+}
+
+// Longest run of fixed bytes is looked up first, the rest of pattern is checked around it
+fn find_masked(pattern: &[MaskedByte], text: &[u8], overlap: Overlap) -> Vec<Range<usize>> {
+    let mut found: Vec<Range<usize>> = vec![];
+    if pattern.is_empty() || pattern.len() > text.len() {
+        return found;
+    }
+
+    let (mut anchor, mut run) = (0..0, 0..0);
+    for (i, byte) in pattern.iter().enumerate() {
+        match byte.fixed() {
+            Some(_) if run.is_empty() => run = i..i + 1,
+            Some(_) => run.end = i + 1,
+            None => run = i + 1..i + 1,
+        }
+        if run.len() > anchor.len() {
+            anchor = run.clone();
+        }
+    }
+
+    let matches_at = |start: usize| {
+        pattern
+            .iter()
+            .zip(&text[start..start + pattern.len()])
+            .all(|(masked, byte)| masked.matches(*byte))
+    };
+    let mut push = |start: usize| {
+        let allowed = match overlap {
+            Overlap::Allowed => true,
+            Overlap::Skipped => found.last().is_none_or(|last| last.end <= start),
+        };
+        if allowed && matches_at(start) {
+            found.push(start..start + pattern.len());
+        }
+    };
+
+    // Only wildcards, every position has to be checked
+    if anchor.is_empty() {
+        for start in 0..=text.len() - pattern.len() {
+            push(start);
+        }
+        return found;
+    }
+
+    let fixed: Vec<u8> = pattern[anchor.clone()]
+        .iter()
+        .filter_map(|byte| byte.fixed())
+        .collect();
+    let finder = Finder::new(&fixed);
+    let last_start = text.len() - pattern.len();
+
+    // Anchor occurrences may overlap, each of them is a candidate
+    let mut from = 0;
+    while let Some(i) = finder.find(&text[from..]) {
+        let at = from + i;
+        from = at + 1;
+
+        let Some(start) = at.checked_sub(anchor.start) else {
+            continue;
+        };
+        if start > last_start {
+            break;
+        }
+        push(start);
+    }
+
+    found
+}
+
+// Empty matches can't be shown, so they are left out
+fn find_regex(regex: &Regex, text: &[u8], from: usize) -> Vec<Range<usize>> {
+    let mut found = vec![];
+    let mut at = from;
+
+    while at <= text.len() {
+        let Some(found_match) = regex.find_at(text, at) else {
+            break;
+        };
+        let range = found_match.range();
+
+        at = range.end.max(range.start + 1);
+        if !range.is_empty() {
+            found.push(range);
+        }
+    }
+
+    found
+}
@@ -0,0 +1,91 @@
+use std::{
+    fs::File,
+    io,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    buffer::{EditBuffer, Origin},
+    misc::{put_cursor_at_index, FileStamp, Parameters, StatusMode, TermState},
+};
+
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+fn rows_visible(state: &TermState) -> usize {
+    // Last terminal row is taken by status bar
+    (state.term_height as usize).saturating_sub(2).max(1)
+}
+
+fn last_offset(state: &TermState, parameters: &Parameters) -> usize {
+    state.buffer.len().saturating_sub(1) / parameters.byte_size as usize
+}
+
+fn is_end_visible(state: &TermState, parameters: &Parameters) -> bool {
+    last_offset(state, parameters) < state.render_from_offset + rows_visible(state)
+}
+
+// Last row ends up at the bottom of the screen with cursor on the last byte
+pub fn scroll_to_end(state: &mut TermState, parameters: &Parameters) {
+    state.render_from_offset =
+        last_offset(state, parameters).saturating_sub(rows_visible(state) - 1);
+    put_cursor_at_index(state, state.buffer.len().saturating_sub(1), parameters);
+}
+
+fn stop(state: &mut TermState, reason: &str) {
+    state.follow = false;
+    state.status_mode = StatusMode::Error(format!("{}, following stopped", reason));
+}
+
+// Polls followed file and loads bytes appended to it
+pub struct Follower {
+    last_poll: Instant,
+}
+
+impl Follower {
+    pub fn new() -> Self {
+        Self {
+            last_poll: Instant::now(),
+        }
+    }
+
+    // True when view has to be redrawn
+    pub fn update(&mut self, state: &mut TermState, parameters: &Parameters) -> io::Result<bool> {
+        if !state.follow || self.last_poll.elapsed() < FOLLOW_INTERVAL {
+            return Ok(false);
+        }
+        self.last_poll = Instant::now();
+
+        let Some(stamp) = FileStamp::of(&state.file_path) else {
+            stop(state, "File is gone");
+            return Ok(true);
+        };
+
+        let Some(loaded) = &state.stamp else {
+            state.stamp = Some(stamp);
+            return Ok(false);
+        };
+
+        if stamp == *loaded {
+            return Ok(false);
+        }
+
+        // Only appending keeps already loaded bytes valid
+        if !stamp.is_same_file(loaded) || stamp.size() < loaded.size() {
+            stop(state, "File was rewritten");
+            return Ok(true);
+        }
+
+        let at_end = is_end_visible(state, parameters);
+
+        let file = File::open(&state.file_path)?;
+        state.buffer.grow(Origin::open(file)?);
+        state.stamp = Some(stamp);
+
+        // User who scrolled away is not pulled back
+        if at_end {
+            scroll_to_end(state, parameters);
+        }
+
+        Ok(true)
+    }
+}
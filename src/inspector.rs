@@ -0,0 +1,385 @@
+use std::fmt::{Display, LowerExp};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Field {
+    Binary,
+    U8,
+    I8,
+    U16(Endian),
+    I16(Endian),
+    U32(Endian),
+    I32(Endian),
+    U64(Endian),
+    I64(Endian),
+    F32(Endian),
+    F64(Endian),
+    // Seconds since epoch stored as u32 little endian
+    Unix,
+    // Microsoft layout, first three groups are little endian
+    Guid,
+}
+
+pub const FIELDS: [Field; 21] = [
+    Field::Binary,
+    Field::U8,
+    Field::I8,
+    Field::U16(Endian::Little),
+    Field::U16(Endian::Big),
+    Field::I16(Endian::Little),
+    Field::I16(Endian::Big),
+    Field::U32(Endian::Little),
+    Field::U32(Endian::Big),
+    Field::I32(Endian::Little),
+    Field::I32(Endian::Big),
+    Field::U64(Endian::Little),
+    Field::U64(Endian::Big),
+    Field::I64(Endian::Little),
+    Field::I64(Endian::Big),
+    Field::F32(Endian::Little),
+    Field::F32(Endian::Big),
+    Field::F64(Endian::Little),
+    Field::F64(Endian::Big),
+    Field::Unix,
+    Field::Guid,
+];
+
+// Reads value of given type from bytes stored in given order
+macro_rules! ordered {
+    ($endian:expr, $ty:ty, $bytes:expr) => {{
+        let array: [u8; std::mem::size_of::<$ty>()] = $bytes.try_into().ok()?;
+        match $endian {
+            Endian::Little => <$ty>::from_le_bytes(array),
+            Endian::Big => <$ty>::from_be_bytes(array),
+        }
+    }};
+}
+
+macro_rules! to_bytes {
+    ($endian:expr, $value:expr) => {
+        match $endian {
+            Endian::Little => $value.to_le_bytes().to_vec(),
+            Endian::Big => $value.to_be_bytes().to_vec(),
+        }
+    };
+}
+
+impl Field {
+    pub fn size(&self) -> usize {
+        match self {
+            Field::Binary | Field::U8 | Field::I8 => 1,
+            Field::U16(_) | Field::I16(_) => 2,
+            Field::U32(_) | Field::I32(_) | Field::F32(_) | Field::Unix => 4,
+            Field::U64(_) | Field::I64(_) | Field::F64(_) => 8,
+            Field::Guid => 16,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        let (name, endian) = match self {
+            Field::Binary => return String::from("binary"),
+            Field::U8 => return String::from("u8"),
+            Field::I8 => return String::from("i8"),
+            Field::Unix => return String::from("unix time"),
+            Field::Guid => return String::from("guid"),
+            Field::U16(endian) => ("u16", endian),
+            Field::I16(endian) => ("i16", endian),
+            Field::U32(endian) => ("u32", endian),
+            Field::I32(endian) => ("i32", endian),
+            Field::U64(endian) => ("u64", endian),
+            Field::I64(endian) => ("i64", endian),
+            Field::F32(endian) => ("f32", endian),
+            Field::F64(endian) => ("f64", endian),
+        };
+
+        match endian {
+            Endian::Little => format!("{} le", name),
+            Endian::Big => format!("{} be", name),
+        }
+    }
+
+    // None when there is not enough bytes left for this representation
+    pub fn format(&self, bytes: &[u8]) -> Option<String> {
+        let bytes = bytes.get(..self.size())?;
+
+        let formatted = match *self {
+            Field::Binary => format!("{:08b}", bytes[0]),
+            Field::U8 => bytes[0].to_string(),
+            Field::I8 => (bytes[0] as i8).to_string(),
+            Field::U16(endian) => ordered!(endian, u16, bytes).to_string(),
+            Field::I16(endian) => ordered!(endian, i16, bytes).to_string(),
+            Field::U32(endian) => ordered!(endian, u32, bytes).to_string(),
+            Field::I32(endian) => ordered!(endian, i32, bytes).to_string(),
+            Field::U64(endian) => ordered!(endian, u64, bytes).to_string(),
+            Field::I64(endian) => ordered!(endian, i64, bytes).to_string(),
+            Field::F32(endian) => format_float(ordered!(endian, f32, bytes)),
+            Field::F64(endian) => format_float(ordered!(endian, f64, bytes)),
+            Field::Unix => format_unix_time(ordered!(Endian::Little, u32, bytes) as i64),
+            Field::Guid => format!(
+                "{{{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{}}}",
+                ordered!(Endian::Little, u32, &bytes[0..4]),
+                ordered!(Endian::Little, u16, &bytes[4..6]),
+                ordered!(Endian::Little, u16, &bytes[6..8]),
+                bytes[8],
+                bytes[9],
+                bytes[10..16]
+                    .iter()
+                    .map(|byte| format!("{:02X}", byte))
+                    .collect::<String>()
+            ),
+        };
+
+        Some(formatted)
+    }
+
+    pub fn parse(&self, input: &str) -> Result<Vec<u8>, String> {
+        let input = input.trim();
+        let invalid = || format!("'{}' is not a valid {}", input, self.label());
+
+        let bytes = match *self {
+            Field::Binary => vec![u8::from_str_radix(input, 2).map_err(|_| invalid())?],
+            Field::U8 => vec![parse_integer::<u8>(input).ok_or_else(invalid)?],
+            Field::I8 => parse_integer::<i8>(input)
+                .ok_or_else(invalid)?
+                .to_le_bytes()
+                .to_vec(),
+            Field::U16(endian) => {
+                to_bytes!(endian, parse_integer::<u16>(input).ok_or_else(invalid)?)
+            }
+            Field::I16(endian) => {
+                to_bytes!(endian, parse_integer::<i16>(input).ok_or_else(invalid)?)
+            }
+            Field::U32(endian) => {
+                to_bytes!(endian, parse_integer::<u32>(input).ok_or_else(invalid)?)
+            }
+            Field::I32(endian) => {
+                to_bytes!(endian, parse_integer::<i32>(input).ok_or_else(invalid)?)
+            }
+            Field::U64(endian) => {
+                to_bytes!(endian, parse_integer::<u64>(input).ok_or_else(invalid)?)
+            }
+            Field::I64(endian) => {
+                to_bytes!(endian, parse_integer::<i64>(input).ok_or_else(invalid)?)
+            }
+            Field::F32(endian) => to_bytes!(endian, input.parse::<f32>().map_err(|_| invalid())?),
+            Field::F64(endian) => to_bytes!(endian, input.parse::<f64>().map_err(|_| invalid())?),
+            Field::Unix => {
+                let seconds = match parse_integer::<u32>(input) {
+                    Some(seconds) => seconds as i64,
+                    None => parse_unix_time(input).ok_or_else(invalid)?,
+                };
+                let seconds = u32::try_from(seconds).map_err(|_| invalid())?;
+                seconds.to_le_bytes().to_vec()
+            }
+            Field::Guid => parse_guid(input).ok_or_else(invalid)?,
+        };
+
+        Ok(bytes)
+    }
+}
+
+// Scientific notation for values which would not fit in the pane otherwise.
+// Generic, so that f32 is printed with its own precision instead of that of f64.
+fn format_float<T: Display + LowerExp + Into<f64> + Copy>(value: T) -> String {
+    let magnitude = value.into().abs();
+    if magnitude != 0.0 && !(1e-6..1e16).contains(&magnitude) {
+        return format!("{:e}", value);
+    }
+    value.to_string()
+}
+
+fn parse_integer<T: TryFrom<i128>>(input: &str) -> Option<T> {
+    let (negative, digits) = match input.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, input),
+    };
+
+    let value = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => i128::from_str_radix(hex, 16).ok()?,
+        None => digits.parse::<i128>().ok()?,
+    };
+
+    let value = if negative { -value } else { value };
+    T::try_from(value).ok()
+}
+
+fn parse_guid(input: &str) -> Option<Vec<u8>> {
+    let digits: String = input
+        .chars()
+        .filter(|char| !matches!(char, '{' | '}' | '-'))
+        .collect();
+
+    if digits.len() != 32 {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(16);
+    for i in (0..32).step_by(2) {
+        bytes.push(u8::from_str_radix(digits.get(i..i + 2)?, 16).ok()?);
+    }
+
+    // First three groups are stored little endian
+    bytes[0..4].reverse();
+    bytes[4..6].reverse();
+    bytes[6..8].reverse();
+
+    Some(bytes)
+}
+
+// Days since 1970-01-01 for given date in proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+fn format_unix_time(seconds: i64) -> String {
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86400));
+    let time = seconds.rem_euclid(86400);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+// Accepts "YYYY-MM-DD HH:MM:SS" with optional " UTC" suffix
+fn parse_unix_time(input: &str) -> Option<i64> {
+    let input = input.trim_end_matches("UTC").trim();
+    let (date, time) = input.split_once(' ').unwrap_or((input, "00:00:00"));
+
+    let date: Vec<i64> = date
+        .split('-')
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    let time: Vec<i64> = time
+        .split(':')
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+
+    if date.len() != 3 || time.len() != 3 {
+        return None;
+    }
+
+    let days = days_from_civil(date[0], date[1], date[2]);
+    Some(days * 86400 + time[0] * 3600 + time[1] * 60 + time[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Random;
+
+    #[test]
+    fn formatted_fields_parse_back() {
+        let mut random = Random(11);
+        for _ in 0..2000 {
+            let bytes = random.bytes(16);
+
+            for field in FIELDS {
+                let formatted = field.format(&bytes).unwrap();
+                let parsed = field.parse(&formatted).unwrap();
+
+                // NaN payload is not kept by its text form
+                let nan = formatted.contains("NaN");
+                if !nan {
+                    assert_eq!(parsed, bytes[..field.size()], "{}", field.label());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fields_need_enough_bytes() {
+        for field in FIELDS {
+            assert_eq!(field.format(&vec![0; field.size() - 1]), None);
+        }
+    }
+
+    #[test]
+    fn known_values() {
+        let field = |field: Field, input: &str| field.parse(input).unwrap();
+
+        assert_eq!(field(Field::U16(Endian::Big), "0x1234"), [0x12, 0x34]);
+        assert_eq!(field(Field::I16(Endian::Little), "-2"), [0xFE, 0xFF]);
+        assert_eq!(
+            field(Field::F32(Endian::Little), "1.1"),
+            1.1f32.to_le_bytes()
+        );
+        assert_eq!(
+            Field::F32(Endian::Little).format(&1.1f32.to_le_bytes()),
+            Some(String::from("1.1"))
+        );
+        assert_eq!(
+            Field::Unix.format(&[0, 0, 0, 0]),
+            Some(String::from("1970-01-01 00:00:00 UTC"))
+        );
+        assert_eq!(
+            field(Field::Unix, "2038-01-19 03:14:07"),
+            i32::MAX.to_le_bytes()
+        );
+        assert_eq!(
+            field(Field::Guid, "{00112233-4455-6677-8899-AABBCCDDEEFF}"),
+            [
+                0x33, 0x22, 0x11, 0x00, 0x55, 0x44, 0x77, 0x66, 0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD,
+                0xEE, 0xFF
+            ]
+        );
+
+        assert!(Field::U8.parse("256").is_err());
+        assert!(Field::I8.parse("-129").is_err());
+        assert!(Field::Unix.parse("1969-12-31 23:59:59").is_err());
+        assert!(Field::Guid.parse("{0011}").is_err());
+    }
+
+    #[test]
+    fn civil_dates_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11017);
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(19782), (2024, 2, 29));
+
+        for days in (-1_000_000..1_000_000).step_by(7) {
+            let (year, month, day) = civil_from_days(days);
+            assert!((1..=12).contains(&month) && (1..=31).contains(&day));
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+}
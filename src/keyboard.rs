@@ -11,9 +11,11 @@ use std::{
 
 use crate::{
     actions::{
-        edit, general_status, go_down, go_left, go_right, go_to_end, go_to_start, go_up, help,
-        next_change, next_found, prev_change, prev_found, quit, remove, save, scroll_down,
-        scroll_up, search,
+        cancel_search, compare, copy, diff, edit, export, follow, general_status, go_down, go_left,
+        go_right, go_to_end, go_to_start, go_up, help, insert, inspect, inspector, next_change,
+        next_diff, next_found, next_tab, paste, paste_insert, prev_change, prev_diff, prev_found,
+        prev_tab, quit, redo, register, remove, save, save_as, scroll_down, scroll_up, search,
+        select, select_down, select_left, select_right, select_up, undo,
     },
     misc::Parameters,
     modes::Modes,
@@ -46,8 +48,7 @@ impl<'a> Keyboard<'a> {
         let file = File::open(key_path).expect("Failed to open config file");
         let reader = BufReader::new(file);
 
-        let mut iter = 0;
-        for line in reader.lines() {
+        for (iter, line) in reader.lines().enumerate() {
             if line.is_err() {
                 panic!("Failed to read line: '{}' from config file", iter + 1)
             }
@@ -78,8 +79,6 @@ impl<'a> Keyboard<'a> {
 
             pairs.insert(event, matched_action);
             help.push(format!("{}: {}", key_str, desc));
-
-            iter += 1;
         }
         Self {
             keys_and_actions: pairs,
@@ -88,7 +87,7 @@ impl<'a> Keyboard<'a> {
     }
 
     pub fn get(&self, code: &KeyEvent) -> Option<&&KeyAction> {
-        self.keys_and_actions.get(&code)
+        self.keys_and_actions.get(code)
     }
 
     pub fn help(&self, separator: &str) -> String {
@@ -127,9 +126,9 @@ fn match_key(key: &str) -> KeyCode {
 
         let number = chars[1]
             .parse::<u8>()
-            .expect(&format!("Failed to parse key: '{}'", key));
+            .unwrap_or_else(|_| panic!("Failed to parse key: '{}'", key));
 
-        if number > 12 || number < 1 {
+        if !(1..=12).contains(&number) {
             panic!("Failed to parse key: '{}'", key);
         }
 
@@ -168,33 +167,69 @@ fn match_action<'b>(action: &str) -> (&'b KeyAction, &str) {
         "quit" => (&quit, "quit"),
         "exit" => (&quit, "quit"),
         "goto" => (&|_, _| Modes::GoTo, "Go to"),
-        "delete" => (&remove, "Remove byte"),
-        "edit" => (&edit, "Change byte"),
+        "delete" => (&remove, "Remove byte or selection"),
+        "select" => (&select, "Start/stop selecting bytes"),
+        "select_left" => (&select_left, "extends selection to the previous element"),
+        "select_right" => (&select_right, "extends selection to the next element"),
+        "select_up" => (&select_up, "extends selection up an offset"),
+        "select_down" => (&select_down, "extends selection down an offset"),
+        "copy" => (&copy, "Copy byte or selection into register"),
+        "yank" => (&copy, "Copy byte or selection into register"),
+        "paste" => (&paste, "Paste register over bytes at cursor"),
+        "paste_insert" => (&paste_insert, "Insert register before cursor"),
+        "register" => (&register, "Choose register for next copy/paste"),
+        "inspector" => (&inspector, "Show/hide data inspector"),
+        "inspect" => (&inspect, "Edit value in data inspector"),
+        "export" => (&export, "Export byte or selection to file"),
+        "edit" => (
+            &edit,
+            "Change byte, fill selection or type text in decoded column",
+        ),
+        "insert" => (&insert, "Insert bytes before cursor"),
+        "follow" => (&follow, "Follow file as it grows"),
+        "next_tab" => (&next_tab, "Shows next open file"),
+        "prev_tab" => (&prev_tab, "Shows previous open file"),
         "save" => (&save, "Save changes"),
+        "save_as" => (&save_as, "Save changes to another file"),
+        "undo" => (&undo, "Undo last change"),
+        "redo" => (&redo, "Redo undone change"),
         "help" => (&help, "Print help"),
         "next_change" => (&next_change, "Goes to next change"),
         "prev_change" => (&prev_change, "Goes to previous change"),
+        "diff" => (&diff, "Compare with next open file side by side"),
+        "compare" => (
+            &compare,
+            "Align with next open file, showing inserted and deleted blocks",
+        ),
+        "next_diff" => (&next_diff, "Goes to next difference between compared files"),
+        "prev_diff" => (
+            &prev_diff,
+            "Goes to previous difference between compared files",
+        ),
         "next_found" => (&next_found, "Goes to next found sequence"),
         "prev_found" => (&prev_found, "Goes to previous found sequence"),
         "go_to_start" => (&go_to_start, "Goes to first offset"),
         "go_to_end" => (&go_to_end, "Goes to last offset"),
         "general_status" => (&general_status, "changes status bar to its general state"),
         "search" => (&search, "Search for sequence"),
+        "cancel_search" => (
+            &cancel_search,
+            "Stop running search, keeping what was found",
+        ),
         _ => panic!("Unrecognized action: '{}'", action),
     }
 }
 
 fn create_config(path: &PathBuf) -> PathBuf {
-    fs::create_dir_all(path).expect(&format!("Failed to create config dir: '{:?}'", &path));
+    fs::create_dir_all(path)
+        .unwrap_or_else(|_| panic!("Failed to create config dir: '{:?}'", &path));
 
     let mut key_path = path.clone();
 
     key_path.push("keys");
 
-    let mut file = File::create(&key_path).expect(&format!(
-        "Failed to create default config file at: {:?}",
-        &key_path
-    ));
+    let mut file = File::create(&key_path)
+        .unwrap_or_else(|_| panic!("Failed to create default config file at: {:?}", &key_path));
 
     let mut keys = String::from("");
     keys += "left    go_left\n";
@@ -207,14 +242,38 @@ fn create_config(path: &PathBuf) -> PathBuf {
     keys += "h       help\n";
     keys += "f2      edit\n";
     keys += "f3      delete\n";
+    keys += "v       select\n";
+    keys += "shift+left  select_left\n";
+    keys += "shift+right select_right\n";
+    keys += "shift+up    select_up\n";
+    keys += "shift+down  select_down\n";
+    keys += "c       copy\n";
+    keys += "o       paste\n";
+    keys += "i       paste_insert\n";
+    keys += "r       register\n";
+    keys += "tab     inspector\n";
+    keys += "ctrl+e  inspect\n";
+    keys += "e       export\n";
+    keys += "insert  insert\n";
     keys += "f5      save\n";
+    keys += "f6      save_as\n";
+    keys += "t       follow\n";
+    keys += "]       next_tab\n";
+    keys += "[       prev_tab\n";
+    keys += "ctrl+z  undo\n";
+    keys += "ctrl+y  redo\n";
     keys += "1       general_status\n";
     keys += "shift+: goto\n";
     keys += "n       next_change\n";
     keys += "ctrl+n  next_found\n";
     keys += "p       prev_change\n";
     keys += "ctrl+p  prev_found\n";
+    keys += "d       diff\n";
+    keys += "alt+n   next_diff\n";
+    keys += "alt+p   prev_diff\n";
+    keys += "ctrl+d  compare\n";
     keys += "f       search\n";
+    keys += "esc     cancel_search\n";
     keys += "home    go_to_start\n";
     keys += "end     go_to_end\n";
 
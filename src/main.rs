@@ -1,33 +1,66 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
     env::{self},
     fs::{File, OpenOptions},
-    io::{stdout, Read, Write},
+    io::{self, stdout, ErrorKind, Write},
+    mem, process,
     time::Duration,
 };
 
+use buffer::{Origin, PieceTable};
 use crossterm::terminal;
 use crossterm::{
     cursor,
-    event::{poll, read, Event},
+    event::{self, poll, read, Event},
     execute,
     terminal::ClearType,
 };
+use follow::Follower;
 use keyboard::Keyboard;
-use misc::{Dimensions, Parameters, StatusMode, TermState};
-use modes::{BytesMode, ChangeMode, GoToMode, HelpMode, Mode, Modes, SearchMode};
+use misc::{
+    is_changed_on_disk, Dimensions, Parameters, StatusMode, Tab, TermState, DEFAULT_REGISTER,
+};
+use modes::{
+    BytesMode, ChangeMode, CompareMode, ConfirmMode, ExportMode, ExternalMode, GoToMode, HelpMode,
+    InsertMode, InspectorMode, Mode, Modes, PasteMode, RecoverMode, RegisterMode, SaveAsMode,
+    SearchMode,
+};
+use swap::SwapWriter;
 
 mod actions;
+mod align;
+mod buffer;
+mod dump;
+mod find;
+mod follow;
+mod inspector;
 mod keyboard;
 mod misc;
 mod modes;
+mod search_job;
 mod string;
+mod swap;
+#[cfg(test)]
+mod testing;
 
 fn print_help() {
     println!("Hex editor - simple terminal based bytes editor");
     println!("Usage:");
-    println!("\t./hex-rs <file-path> <number-of-bytes-shown-in-one-row>");
+    println!("\t./hex-rs <file-path>... <number-of-bytes-shown-in-one-row> [options]");
     println!("\teg. ./hex-rs ./cat.png 16");
+    println!("\teg. ./hex-rs ./cat.png ./dog.png");
+    println!("\teg. ./hex-rs ./cat.png --xxd --offset=0x100 --length=64");
+    println!("\teg. ./hex-rs ./cat.txt ./cat.png --reverse");
+    println!("Options:");
+    println!("\t--backup   keep copy of the original file as <file-path>.bak when saving");
+    println!("\t--readonly only view the file, editing and saving are disabled");
+    println!("\t--dump     print bytes to stdout instead of opening the editor");
+    println!("\t--xxd      same as --dump, but formatted like xxd output");
+    println!("\t--offset=N with --dump, start at byte N (decimal or 0x prefixed hex)");
+    println!("\t--length=N with --dump, print at most N bytes");
+    println!("\t--cols=N   number of bytes shown in one row");
+    println!("\t--reverse  rebuild binary from hex-rs or xxd dump: <dump-path> [output-path],");
+    println!("\t           written to stdout when output path is not given");
     println!("Config:");
     println!("Config file can be found in: ");
     println!("\tWindows: C:\\Users\\Me\\AppData\\Roaming\\Papilionem\\Hex editor\\config\\");
@@ -38,6 +71,39 @@ fn print_help() {
     );
 }
 
+// Falls back to read-only viewing when file is not writable
+fn open_file(file_path: &str, readonly: bool) -> io::Result<(File, bool)> {
+    if !readonly {
+        match OpenOptions::new().read(true).write(true).open(file_path) {
+            Ok(file) => return Ok((file, false)),
+            Err(error)
+                if matches!(
+                    error.kind(),
+                    ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem
+                ) => {}
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok((File::open(file_path)?, true))
+}
+
+// Coming back to the editor is when changes made by other programs are likely
+fn check_on_disk(state: &mut TermState, mode: Modes) -> Modes {
+    // Followed file is expected to change, Follower picks new bytes up
+    if state.follow || !is_changed_on_disk(state) {
+        return mode;
+    }
+
+    // Other modes are not interrupted, warning is shown instead
+    if mode != Modes::Bytes {
+        state.status_mode = StatusMode::Error(String::from("File was changed on disk"));
+        return mode;
+    }
+
+    Modes::External
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     for arg in env::args() {
         if arg == "-h" || arg == "--help" {
@@ -48,15 +114,58 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let parameters = Parameters::from(env::args());
 
-    if parameters.file_path.is_empty() {
+    if parameters.file_paths.is_empty() {
         println!("File path argument is missing");
         return Ok(());
     }
 
+    if parameters.reverse {
+        if let Err(error) = dump::reverse(&parameters) {
+            eprintln!("Failed to rebuild from dump: {}", error);
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if parameters.dump {
+        if let Err(error) = dump::dump(&parameters) {
+            eprintln!("Failed to dump {}", error);
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let mut files = vec![];
+    for file_path in &parameters.file_paths {
+        let (file, readonly) = match open_file(file_path, parameters.readonly) {
+            Ok(opened) => opened,
+            Err(error) => {
+                println!("Failed to open '{}': {}", file_path, error);
+                return Ok(());
+            }
+        };
+
+        let origin = match Origin::open(file) {
+            Ok(origin) => origin,
+            Err(error) => {
+                println!("Failed to read '{}': {}", file_path, error);
+                return Ok(());
+            }
+        };
+
+        files.push((PieceTable::new(origin), readonly));
+    }
+
     let mut stdout = stdout();
 
     //Enter terminal application mode
-    execute!(&mut stdout, terminal::EnterAlternateScreen)?;
+    execute!(
+        &mut stdout,
+        terminal::EnterAlternateScreen,
+        event::EnableMouseCapture,
+        event::EnableBracketedPaste,
+        event::EnableFocusChange
+    )?;
     terminal::enable_raw_mode()?;
 
     let size = terminal::size()?;
@@ -64,52 +173,120 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let dimensions = Dimensions::new(padding, &parameters);
     let keyboard = Keyboard::new();
 
-    let mut file = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .append(false)
-        .open(parameters.file_path.clone())
-        .expect("Failed to open file");
+    let mut tabs: Vec<Tab> = files
+        .into_iter()
+        .zip(&parameters.file_paths)
+        .map(|((buffer, readonly), file_path)| {
+            Tab::new(buffer, file_path.clone(), readonly, dimensions.bytes.0)
+        })
+        .collect();
 
-    let file_size = file.metadata()?.len();
-    let mut bytes: Vec<u8> = vec![0; file_size as usize];
-    file.read(&mut bytes)
-        .expect("Failed to read bytes into buffer");
+    // First file starts active, its tab keeps an empty placeholder
+    let placeholder = Tab::new(
+        PieceTable::new(Origin::Memory(vec![])),
+        String::from(""),
+        false,
+        dimensions.bytes.0,
+    );
+    let first = mem::replace(&mut tabs[0], placeholder);
 
     let mut state = TermState {
-        row: 1,
-        column: dimensions.bytes.0,
+        row: first.row,
+        column: first.column,
         term_height: size.1,
         term_width: size.0,
         padding,
-        render_from_offset: 0,
+        render_from_offset: first.render_from_offset,
         status_mode: StatusMode::General,
         dimensions: &dimensions,
         prev_mode: Modes::Bytes,
-        bytes_changed: HashSet::new(),
-        bytes_removed: HashSet::new(),
-        bytes,
-        found_sequences: HashSet::new(),
-        file_path: &parameters.file_path,
+        buffer: first.buffer,
+        found_sequences: first.found_sequences,
+        file_path: first.file_path,
+        selection_anchor: first.selection_anchor,
+        registers: HashMap::new(),
+        register: DEFAULT_REGISTER,
+        pasted: String::from(""),
+        inspector: false,
+        after_confirm: Modes::Bytes,
+        readonly: first.readonly,
+        stamp: first.stamp,
+        follow: first.follow,
+        tabs,
+        active_tab: 0,
+        diff_with: None,
+        comparison: None,
+        search: None,
     };
 
     // Modes
-    let mut bytes_mode = BytesMode::new(&keyboard, &parameters, file_size as usize)?;
+    let mut bytes_mode = BytesMode::new(&keyboard, &parameters)?;
     let mut help_mode = HelpMode::new(padding, &keyboard);
     let mut change_mode = ChangeMode::new(&parameters);
     let mut search_mode = SearchMode::new();
     let mut goto_mode = GoToMode::new();
-    let modes: [&mut dyn Mode; 5] = [
+    let mut insert_mode = InsertMode::new(&parameters);
+    let mut export_mode = ExportMode::new();
+    let mut register_mode = RegisterMode::new();
+    let mut paste_mode = PasteMode::new();
+    let mut inspector_mode = InspectorMode::new(&parameters);
+    let mut confirm_mode = ConfirmMode::new();
+    let mut save_as_mode = SaveAsMode::new();
+    let mut recover_mode = RecoverMode::new(&parameters);
+    let mut external_mode = ExternalMode::new();
+    let mut compare_mode = CompareMode::new(&parameters);
+    let modes: [&mut dyn Mode; 15] = [
         &mut bytes_mode,
         &mut help_mode,
         &mut change_mode,
         &mut goto_mode,
         &mut search_mode,
+        &mut insert_mode,
+        &mut export_mode,
+        &mut register_mode,
+        &mut paste_mode,
+        &mut inspector_mode,
+        &mut confirm_mode,
+        &mut save_as_mode,
+        &mut recover_mode,
+        &mut external_mode,
+        &mut compare_mode,
     ];
 
     let mut index = 0;
+    let mut mode = Modes::Bytes;
+    if !state.readonly && swap::is_recoverable(&state) {
+        index = 12;
+        mode = Modes::Recover;
+    }
+    let mut swap_writer = SwapWriter::new(&state);
+    let mut follower = Follower::new();
+
+    modes[index].draw(&mut stdout, &state)?;
+    stdout.flush()?;
 
     loop {
+        if let Err(error) = swap_writer.update(&state) {
+            state.status_mode = StatusMode::Error(format!("Failed to write swap file: {}", error));
+        }
+
+        match follower.update(&mut state, &parameters) {
+            Ok(true) => {
+                modes[index].draw(&mut stdout, &state)?;
+                stdout.flush()?;
+            }
+            Ok(false) => {}
+            Err(error) => {
+                state.follow = false;
+                state.status_mode = StatusMode::Error(format!("Failed to follow file: {}", error));
+            }
+        }
+
+        if search_job::update(&mut state) {
+            modes[index].draw(&mut stdout, &state)?;
+            stdout.flush()?;
+        }
+
         if poll(Duration::from_millis(16))? {
             let new_mode = match read()? {
                 Event::Key(event) => modes[index].handle_input(&event, &mut state, &parameters)?,
@@ -123,9 +300,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     &mut state,
                     &parameters,
                 )?,
-                Event::FocusGained => todo!(),
-                Event::FocusLost => todo!(),
-                Event::Paste(_) => todo!(),
+                Event::FocusGained => check_on_disk(&mut state, mode),
+                Event::FocusLost => continue,
+                Event::Paste(text) => modes[index].handle_paste(&text, &mut state, &parameters)?,
             };
 
             let new_index = match new_mode {
@@ -134,12 +311,23 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 Modes::Change => 2,
                 Modes::GoTo => 3,
                 Modes::Search => 4,
+                Modes::Insert => 5,
+                Modes::Export => 6,
+                Modes::Register => 7,
+                Modes::Paste => 8,
+                Modes::Inspector => 9,
+                Modes::Confirm => 10,
+                Modes::SaveAs => 11,
+                Modes::Recover => 12,
+                Modes::External => 13,
+                Modes::Compare => 14,
                 Modes::Quit => break,
             };
 
             if new_index != index {
                 index = new_index;
             }
+            mode = new_mode;
 
             modes[index].draw(&mut stdout, &state)?;
 
@@ -151,9 +339,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     execute!(
         &mut stdout,
         terminal::Clear(ClearType::All),
+        event::DisableMouseCapture,
+        event::DisableBracketedPaste,
+        event::DisableFocusChange,
         terminal::LeaveAlternateScreen,
         cursor::Show,
     )?;
     terminal::disable_raw_mode()?;
+
+    swap_writer.remove_owned(&state)?;
     Ok(())
 }
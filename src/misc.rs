@@ -1,12 +1,11 @@
-use std::{
-    collections::{HashMap, HashSet},
-    env::Args,
-    fs::File,
-    io::{Read, Result},
-    ops::Range,
-};
+use std::{collections::HashMap, env::Args, fs, mem, ops::Range, time::SystemTime};
 
-use crate::modes::Modes;
+use crate::{
+    align::Comparison,
+    buffer::{EditBuffer, PieceTable},
+    modes::Modes,
+    search_job::SearchJob,
+};
 
 pub struct Dimensions {
     pub offsets: (u16, u16),
@@ -44,11 +43,59 @@ pub enum Direction {
 
 pub enum StatusMode {
     General,
+    // Failure of an action which would otherwise go unnoticed, shown until status is reset
+    Error(String),
+}
+
+pub const DEFAULT_REGISTER: char = '"';
+
+// What file looked like when it was loaded, to notice other programs writing to it
+#[derive(PartialEq)]
+pub struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+    inode: u64,
+}
+
+impl FileStamp {
+    // None when file cannot be reached anymore
+    pub fn of(path: &str) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+
+        #[cfg(unix)]
+        let inode = std::os::unix::fs::MetadataExt::ino(&metadata);
+        #[cfg(not(unix))]
+        let inode = 0;
+
+        Some(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            inode,
+        })
+    }
+
+    pub fn is_same_file(&self, other: &FileStamp) -> bool {
+        self.inode == other.inode
+    }
+
+    pub fn size(&self) -> u64 {
+        self.len
+    }
 }
 
 pub struct Parameters {
-    pub file_path: String,
+    pub file_paths: Vec<String>,
     pub byte_size: u16,
+    // Keep copy of the original file as <file>.bak when saving
+    pub backup: bool,
+    pub readonly: bool,
+    // Print bytes to stdout instead of opening the editor
+    pub dump: bool,
+    pub xxd: bool,
+    pub offset: usize,
+    pub length: Option<usize>,
+    // Rebuild binary from dump instead of opening the editor
+    pub reverse: bool,
 }
 
 pub struct TermState<'a> {
@@ -61,152 +108,344 @@ pub struct TermState<'a> {
     pub status_mode: StatusMode,
     pub dimensions: &'a Dimensions,
     pub prev_mode: Modes,
-    //TODO: find a better place for it
-    pub bytes: Vec<u8>,
-    pub bytes_removed: HashSet<usize>,
-    pub bytes_changed: HashSet<usize>,
-    pub found_sequences: HashSet<Range<usize>>,
-    pub file_path: &'a str,
+    pub buffer: PieceTable,
+    // Sorted by position
+    pub found_sequences: Vec<Range<usize>>,
+    pub file_path: String,
+    // Byte index at which selection started, cursor is its other end
+    pub selection_anchor: Option<usize>,
+    pub registers: HashMap<char, Vec<u8>>,
+    // Register used by next copy/paste
+    pub register: char,
+    // Text of the last terminal paste event, waiting for PasteMode to decide on its format
+    pub pasted: String,
+    pub inspector: bool,
+    // Mode to continue in once unsaved changes are saved or discarded
+    pub after_confirm: Modes,
+    // File can only be viewed, either on request or because it is not writable
+    pub readonly: bool,
+    pub stamp: Option<FileStamp>,
+    // Bytes appended to the file by other programs are loaded as they come
+    pub follow: bool,
+    // One per open file, the active one is stale as its content lives in fields above
+    pub tabs: Vec<Tab>,
+    pub active_tab: usize,
+    // Tab shown next to the active one, with differing bytes highlighted
+    pub diff_with: Option<usize>,
+    // Alignment shown by CompareMode, dropped once it is closed
+    pub comparison: Option<Comparison>,
+    pub search: Option<SearchJob>,
 }
 
-impl From<Args> for Parameters {
-    fn from(args: Args) -> Self {
-        let collected_args: Vec<String> = args.collect();
-        let mut byte_size = 16;
+// Everything that belongs to one open file, kept aside while another file is shown
+pub struct Tab {
+    pub row: u16,
+    pub column: u16,
+    pub render_from_offset: usize,
+    pub buffer: PieceTable,
+    pub found_sequences: Vec<Range<usize>>,
+    pub file_path: String,
+    pub selection_anchor: Option<usize>,
+    pub readonly: bool,
+    pub stamp: Option<FileStamp>,
+    pub follow: bool,
+}
 
-        if collected_args.len() < 2 {
-            return Self {
-                file_path: String::from(""),
-                byte_size,
-            };
+impl Tab {
+    pub fn new(buffer: PieceTable, file_path: String, readonly: bool, column: u16) -> Self {
+        Self {
+            row: 1,
+            column,
+            render_from_offset: 0,
+            buffer,
+            found_sequences: vec![],
+            stamp: FileStamp::of(&file_path),
+            file_path,
+            selection_anchor: None,
+            readonly,
+            follow: false,
         }
+    }
+}
 
-        if collected_args.len() >= 3 {
-            byte_size = collected_args[2]
-                .parse()
-                .expect("Second argument must be u16");
+fn swap_tab(state: &mut TermState, tab: &mut Tab) {
+    mem::swap(&mut state.row, &mut tab.row);
+    mem::swap(&mut state.column, &mut tab.column);
+    mem::swap(&mut state.render_from_offset, &mut tab.render_from_offset);
+    mem::swap(&mut state.buffer, &mut tab.buffer);
+    mem::swap(&mut state.found_sequences, &mut tab.found_sequences);
+    mem::swap(&mut state.file_path, &mut tab.file_path);
+    mem::swap(&mut state.selection_anchor, &mut tab.selection_anchor);
+    mem::swap(&mut state.readonly, &mut tab.readonly);
+    mem::swap(&mut state.stamp, &mut tab.stamp);
+    mem::swap(&mut state.follow, &mut tab.follow);
+}
 
-            if byte_size <= 0 {
-                panic!("Byte size should be greater than 0!")
+pub fn switch_tab(state: &mut TermState, to: usize) {
+    if to == state.active_tab || to >= state.tabs.len() {
+        return;
+    }
+
+    // Active file goes back to its tab and the stale content moves on to the new active tab
+    let mut tabs = mem::take(&mut state.tabs);
+    swap_tab(state, &mut tabs[state.active_tab]);
+    swap_tab(state, &mut tabs[to]);
+    state.tabs = tabs;
+
+    // Compared file became active, so the previous one is compared with it instead
+    if state.diff_with == Some(to) {
+        state.diff_with = Some(state.active_tab);
+    }
+    state.active_tab = to;
+}
+
+// Path and buffer of every open file, in tab order
+pub fn open_files<'b>(state: &'b TermState) -> Vec<(&'b str, &'b PieceTable)> {
+    state
+        .tabs
+        .iter()
+        .enumerate()
+        .map(|(i, tab)| match i == state.active_tab {
+            true => (state.file_path.as_str(), &state.buffer),
+            false => (tab.file_path.as_str(), &tab.buffer),
+        })
+        .collect()
+}
+
+impl From<Args> for Parameters {
+    fn from(args: Args) -> Self {
+        let (flags, collected_args): (Vec<String>, Vec<String>) =
+            args.partition(|arg| arg.starts_with("--"));
+        let mut byte_size = 16;
+        let backup = flags.iter().any(|flag| flag == "--backup");
+        let readonly = flags.iter().any(|flag| flag == "--readonly");
+        let xxd = flags.iter().any(|flag| flag == "--xxd");
+        let dump = xxd || flags.iter().any(|flag| flag == "--dump");
+        let offset = flag_number(&flags, "--offset").unwrap_or(0);
+        let length = flag_number(&flags, "--length");
+        let reverse = flags.iter().any(|flag| flag == "--reverse");
+
+        let mut file_paths: Vec<String> = collected_args.into_iter().skip(1).collect();
+
+        // Number after file paths is the row width, file named like a number can be given as ./16
+        if file_paths.len() >= 2 {
+            if let Some(size) = file_paths.last().and_then(|last| last.parse().ok()) {
+                byte_size = size;
+                file_paths.pop();
+
+                if byte_size == 0 {
+                    panic!("Byte size should be greater than 0!")
+                }
             }
         }
 
+        if let Some(cols) = flag_number(&flags, "--cols") {
+            byte_size = u16::try_from(cols)
+                .ok()
+                .filter(|cols| *cols > 0)
+                .unwrap_or_else(|| panic!("Column count should be between 1 and {}", u16::MAX));
+        }
+
         Self {
-            file_path: collected_args[1].clone(),
+            file_paths,
             byte_size,
+            backup,
+            readonly,
+            dump,
+            xxd,
+            offset,
+            length,
+            reverse,
         }
     }
 }
 
+// Value of flag given as --name=value, either decimal or 0x prefixed hex
+fn flag_number(flags: &[String], name: &str) -> Option<usize> {
+    let value = flags
+        .iter()
+        .find_map(|flag| flag.strip_prefix(name)?.strip_prefix('='))?;
+
+    let number = match value.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+
+    Some(number.unwrap_or_else(|_| panic!("Invalid value of {}: '{}'", name, value)))
+}
+
+pub fn get_symbol(byte: u8) -> char {
+    if byte.is_ascii_whitespace() {
+        return ' ';
+    }
+
+    if !byte.is_ascii() || byte.is_ascii_control() {
+        return '.';
+    }
+
+    char::from(byte)
+}
+
+pub fn is_changed_on_disk(state: &TermState) -> bool {
+    FileStamp::of(&state.file_path) != state.stamp
+}
+
 pub fn get_byte_at_cursor(state: &TermState, parameters: &Parameters) -> usize {
     // @Improvement: Move "5" (hex value width + space) to separate variable
     let bytes_section_column = state.dimensions.bytes.0;
-    let actual_row = (state.row + state.render_from_offset as u16) - 1;
+    let actual_row = (state.row as usize + state.render_from_offset) - 1;
 
-    let actual_column = (state.column - bytes_section_column) / 5;
+    let actual_column = match is_cursor_in_decoded(state) {
+        true => (state.column - state.dimensions.decoded.0) as usize,
+        false => ((state.column - bytes_section_column) / 5) as usize,
+    };
 
-    return (actual_row * parameters.byte_size + actual_column) as usize;
+    actual_row * parameters.byte_size as usize + actual_column
 }
 
-pub fn get_index_of_closest_change(pos: usize, state: &TermState, direction: Direction) -> usize {
-    let mut closest_diff = usize::MAX;
-    let mut closest_changed_index = usize::MAX;
+pub fn is_cursor_in_decoded(state: &TermState) -> bool {
+    state.column >= state.dimensions.decoded.0
+}
 
-    match direction {
-        Direction::Left => {
-            for i in &state.bytes_changed {
-                if i == &pos || i > &pos {
-                    continue;
-                }
-                let diff = pos - i;
+pub fn get_byte_at(
+    state: &TermState,
+    column: u16,
+    row: u16,
+    parameters: &Parameters,
+) -> Option<usize> {
+    let (bytes_start, bytes_end) = state.dimensions.bytes;
+    let (decoded_start, decoded_end) = state.dimensions.decoded;
 
-                if diff < closest_diff {
-                    closest_changed_index = *i;
-                    closest_diff = diff;
-                }
-            }
+    if row == 0 || row >= state.term_height {
+        return None;
+    }
 
-            for i in &state.bytes_removed {
-                if i == &pos || i > &pos {
-                    continue;
-                }
+    let actual_row = row as usize - 1 + state.render_from_offset;
+    let actual_column = if column >= bytes_start && column <= bytes_end {
+        ((column - bytes_start) / 5) as usize
+    } else if column >= decoded_start && column < decoded_end {
+        (column - decoded_start) as usize
+    } else {
+        return None;
+    };
 
-                let diff = pos - i;
-                if diff < closest_diff {
-                    closest_changed_index = *i;
-                    closest_diff = diff;
-                }
-            }
-        }
-        Direction::Right => {
-            for i in &state.bytes_changed {
-                if i == &pos || i < &pos {
-                    continue;
-                }
-                let diff = i - pos;
+    Some(actual_row * parameters.byte_size as usize + actual_column)
+}
 
-                if diff < closest_diff {
-                    closest_changed_index = *i;
-                    closest_diff = diff;
-                }
-            }
+pub fn get_selection(state: &TermState, parameters: &Parameters) -> Option<Range<usize>> {
+    let anchor = state.selection_anchor?;
+    let cursor = get_byte_at_cursor(state, parameters);
+    let len = state.buffer.len();
 
-            for i in &state.bytes_removed {
-                if i == &pos || i < &pos {
-                    continue;
-                }
+    Some(anchor.min(cursor).min(len)..(anchor.max(cursor) + 1).min(len))
+}
 
-                let diff = i - pos;
-                if diff < closest_diff {
-                    closest_changed_index = *i;
-                    closest_diff = diff;
-                }
-            }
+// Range actions operate on, selected bytes or the one under cursor
+pub fn get_selection_or_cursor(state: &TermState, parameters: &Parameters) -> Range<usize> {
+    match get_selection(state, parameters) {
+        Some(range) => range,
+        None => {
+            let byte_index = get_byte_at_cursor(state, parameters);
+            byte_index..byte_index + 1
         }
     }
+}
 
-    closest_changed_index
+pub fn get_index_of_closest_change(pos: usize, state: &TermState, direction: Direction) -> usize {
+    let buffer = &state.buffer;
+
+    let closest = match direction {
+        Direction::Left => buffer.spans(0..pos).last().map(|span| span.range.start),
+        Direction::Right => buffer
+            .spans(pos..buffer.len())
+            .iter()
+            .find(|span| span.range.start > pos)
+            .map(|span| span.range.start),
+    };
+
+    closest.unwrap_or(usize::MAX)
 }
 
-pub fn get_index_of_closest_found(pos: usize, state: &TermState, direction: Direction) -> usize {
-    let mut closest_diff = usize::MAX;
-    let mut closest_changed_index = usize::MAX;
+// Buffer of the file compared with active one
+pub fn get_diff_buffer<'b>(state: &'b TermState) -> Option<&'b PieceTable> {
+    state
+        .diff_with
+        .filter(|i| *i != state.active_tab)
+        .and_then(|i| state.tabs.get(i))
+        .map(|tab| &tab.buffer)
+}
 
-    match direction {
-        Direction::Left => {
-            for range in &state.found_sequences {
-                let i = range.start;
+pub fn get_index_of_closest_difference(
+    pos: usize,
+    state: &TermState,
+    direction: Direction,
+) -> usize {
+    const CHUNK: usize = 64 * 1024;
 
-                if i == pos || i > pos {
-                    continue;
-                }
-                let diff = pos - i;
+    let Some(other) = get_diff_buffer(state) else {
+        return usize::MAX;
+    };
+    let buffer = &state.buffer;
+    let end = buffer.len().max(other.len());
 
-                if diff < closest_diff {
-                    closest_changed_index = i;
-                    closest_diff = diff;
+    // Bytes past the end of the shorter file differ too
+    let differs = |i: usize, a: &[u8], b: &[u8]| a.get(i) != b.get(i);
+
+    match direction {
+        Direction::Right => {
+            let mut start = pos + 1;
+            while start < end {
+                let range = start..(start + CHUNK).min(end);
+                let (a, b) = (buffer.read(range.clone()), other.read(range.clone()));
+                if let Some(i) = (0..range.len()).find(|i| differs(*i, &a, &b)) {
+                    return start + i;
                 }
+                start = range.end;
             }
         }
-        Direction::Right => {
-            for range in &state.found_sequences {
-                let i = range.start;
-
-                if i == pos || i < pos {
-                    continue;
-                }
-                let diff = i - pos;
-
-                if diff < closest_diff {
-                    closest_changed_index = i;
-                    closest_diff = diff;
+        Direction::Left => {
+            let mut stop = pos.min(end);
+            while stop > 0 {
+                let range = stop.saturating_sub(CHUNK)..stop;
+                let (a, b) = (buffer.read(range.clone()), other.read(range.clone()));
+                if let Some(i) = (0..range.len()).rev().find(|i| differs(*i, &a, &b)) {
+                    return range.start + i;
                 }
+                stop = range.start;
             }
         }
     }
 
-    closest_changed_index
+    usize::MAX
+}
+
+pub fn get_index_of_closest_found(pos: usize, state: &TermState, direction: Direction) -> usize {
+    let found = &state.found_sequences;
+
+    let closest = match direction {
+        Direction::Left => {
+            let before = found.partition_point(|range| range.start < pos);
+            before.checked_sub(1).map(|i| found[i].start)
+        }
+        Direction::Right => {
+            let after = found.partition_point(|range| range.start <= pos);
+            found.get(after).map(|range| range.start)
+        }
+    };
+
+    closest.unwrap_or(usize::MAX)
+}
+
+// Found sequences are sorted and ones starting later end later too
+pub fn is_found(state: &TermState, index: usize) -> bool {
+    let found = &state.found_sequences;
+    let up_to = found.partition_point(|range| range.start <= index);
+
+    up_to
+        .checked_sub(1)
+        .is_some_and(|i| found[i].contains(&index))
 }
+
 pub fn get_offset_for_index(index: usize, parameters: &Parameters) -> usize {
     index / parameters.byte_size as usize
 }
@@ -224,10 +463,10 @@ pub fn put_cursor_at_index(
     closest_byte_index: usize,
     parameters: &Parameters,
 ) {
-    let closest_byte_offset = get_offset_for_index(closest_byte_index, &parameters);
+    let closest_byte_offset = get_offset_for_index(closest_byte_index, parameters);
 
     let visible_start = state.render_from_offset;
-    let visible_end = (state.render_from_offset as u16 + state.term_height - 2) as usize;
+    let visible_end = state.render_from_offset + (state.term_height - 2) as usize;
 
     if closest_byte_offset >= visible_start && closest_byte_offset <= visible_end {
         state.row = (closest_byte_offset - visible_start) as u16 + 1;
@@ -236,5 +475,13 @@ pub fn put_cursor_at_index(
         state.row = 1;
     }
 
-    state.column = get_column_for_index(closest_byte_index, parameters);
+    // Cursor stays in the column it was in
+    state.column = match is_cursor_in_decoded(state) {
+        true => {
+            let offset = get_offset_for_index(closest_byte_index, parameters);
+            let byte_in_row = closest_byte_index - offset * parameters.byte_size as usize;
+            state.dimensions.decoded.0 + byte_in_row as u16
+        }
+        false => get_column_for_index(closest_byte_index, parameters),
+    };
 }
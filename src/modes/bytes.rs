@@ -1,13 +1,20 @@
 use std::collections::HashMap;
-use std::fs::File;
 use std::io::{Result, Stdout, Write};
 use std::ops::Range;
+use std::path::Path;
 
 use crossterm::event::{self, KeyEvent, MouseEvent};
 use crossterm::style::{Color, SetBackgroundColor, SetForegroundColor};
 use crossterm::terminal::ClearType;
 use crossterm::{cursor, queue, style, terminal};
 
+use crate::actions::is_editable;
+use crate::buffer::{ByteState, EditBuffer};
+use crate::inspector::FIELDS;
+use crate::misc::{
+    get_byte_at, get_byte_at_cursor, get_diff_buffer, get_selection, get_symbol, is_found,
+    open_files, put_cursor_at_index, DEFAULT_REGISTER,
+};
 use crate::StatusMode;
 use crate::{keyboard::Keyboard, Parameters, TermState};
 
@@ -19,7 +26,6 @@ enum BytesScreens {
 pub struct BytesMode<'a> {
     keyboard: &'a Keyboard<'a>,
     parameters: &'a Parameters,
-    offsets: u16,
     minimal_width: u16,
     to_draw: BytesScreens,
 }
@@ -36,18 +42,12 @@ impl<'a> BytesMode<'a> {
         )?;
         Ok(())
     }
-    pub fn new(
-        keyboard: &'a Keyboard,
-        parameters: &'a Parameters,
-        file_size: usize,
-    ) -> Result<BytesMode<'a>> {
-        let offsets = file_size as u16 / parameters.byte_size;
+    pub fn new(keyboard: &'a Keyboard, parameters: &'a Parameters) -> Result<BytesMode<'a>> {
         let minimal_width = ((parameters.byte_size + 1) * 5) + 16;
 
         let mode = BytesMode {
             keyboard,
             parameters,
-            offsets,
             minimal_width,
             to_draw: BytesScreens::Bytes,
         };
@@ -62,7 +62,7 @@ impl<'a> Mode for BytesMode<'a> {
         state: &mut TermState,
         parameters: &Parameters,
     ) -> Result<Modes> {
-        match self.keyboard.get(&event) {
+        match self.keyboard.get(event) {
             Some(action) => Ok(action(state, parameters)),
             None => Ok(Modes::Bytes),
         }
@@ -74,16 +74,36 @@ impl<'a> Mode for BytesMode<'a> {
         state: &mut TermState,
         parameters: &Parameters,
     ) -> Result<Modes> {
+        let byte_index = get_byte_at(state, event.column, event.row, parameters);
+
         match event.kind {
             event::MouseEventKind::ScrollDown => state.render_from_offset += 1,
-            event::MouseEventKind::ScrollUp => state.render_from_offset -= 1,
-            event::MouseEventKind::Up(btn) => match btn {
-                event::MouseButton::Left => {
+            event::MouseEventKind::ScrollUp => {
+                state.render_from_offset = state.render_from_offset.saturating_sub(1)
+            }
+            event::MouseEventKind::Down(event::MouseButton::Left)
+            | event::MouseEventKind::Drag(event::MouseButton::Left)
+            | event::MouseEventKind::Up(event::MouseButton::Left) => {
+                if let Some(byte_index) = byte_index {
+                    // Clicked column decides whether cursor lands on hex or decoded side
                     state.column = event.column;
-                    state.row = event.row;
+                    put_cursor_at_index(state, byte_index, parameters);
+                }
+
+                match event.kind {
+                    event::MouseEventKind::Down(_) if byte_index.is_some() => {
+                        state.selection_anchor = byte_index
+                    }
+                    // Plain click is not a selection
+                    event::MouseEventKind::Up(_)
+                        if state.selection_anchor
+                            == Some(get_byte_at_cursor(state, parameters)) =>
+                    {
+                        state.selection_anchor = None
+                    }
+                    _ => {}
                 }
-                _ => {}
-            },
+            }
             _ => {}
         }
         Ok(Modes::Bytes)
@@ -91,11 +111,11 @@ impl<'a> Mode for BytesMode<'a> {
 
     fn handle_resize(
         &mut self,
-        stdout: &mut Stdout,
+        _stdout: &mut Stdout,
         width: u16,
         height: u16,
         state: &mut TermState,
-        parameters: &Parameters,
+        _parameters: &Parameters,
     ) -> Result<Modes> {
         if width < self.minimal_width {
             self.to_draw = BytesScreens::TooSmall;
@@ -118,14 +138,24 @@ impl<'a> Mode for BytesMode<'a> {
         Ok(Modes::Bytes)
     }
 
+    fn handle_paste(
+        &mut self,
+        text: &str,
+        state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<Modes> {
+        if !is_editable(state) {
+            return Ok(Modes::Bytes);
+        }
+
+        state.pasted = text.to_string();
+        Ok(Modes::Paste)
+    }
+
     fn draw(&self, stdout: &mut Stdout, state: &TermState) -> Result<()> {
         match self.to_draw {
             BytesScreens::Bytes => {
-                queue!(stdout, terminal::Clear(ClearType::All))?;
-
-                draw_fixed_ui(stdout, &state, &self.parameters, &self.keyboard)?;
-                draw_offsets(stdout, &state, &self.parameters, self.offsets)?;
-                draw_bytes(stdout, &state, &self.parameters, &state.bytes)?;
+                draw_view(stdout, state, self.parameters)?;
 
                 queue!(stdout, cursor::MoveTo(state.column, state.row))?;
             }
@@ -138,12 +168,90 @@ impl<'a> Mode for BytesMode<'a> {
     }
 }
 
-fn draw_bytes(
+// Whole bytes screen, shared with modes which edit in place.
+pub(super) fn draw_view(
     stdout: &mut Stdout,
     state: &TermState,
     parameters: &Parameters,
-    bytes: &Vec<u8>,
 ) -> Result<()> {
+    queue!(stdout, terminal::Clear(ClearType::All))?;
+
+    draw_fixed_ui(stdout, state, parameters)?;
+    draw_offsets(stdout, state, parameters)?;
+    draw_bytes(stdout, state, parameters)?;
+    draw_diff(stdout, state, parameters)?;
+
+    if state.inspector {
+        draw_inspector(stdout, state, parameters, None)?;
+    }
+
+    Ok(())
+}
+
+// Interpretations of bytes under cursor, drawn right of decoded column.
+// Editing is index of edited field together with what user typed so far.
+pub(super) fn draw_inspector(
+    stdout: &mut Stdout,
+    state: &TermState,
+    parameters: &Parameters,
+    editing: Option<(usize, &str)>,
+) -> Result<()> {
+    let x = match get_diff_buffer(state) {
+        Some(_) => state.dimensions.decoded.1 + 3 + diff_width(parameters) + 3,
+        None => state.dimensions.decoded.1 + 3,
+    };
+    // Header row is taken by list of open files
+    let top = if tabs_shown(state) { 1 } else { 0 };
+
+    if x + INSPECTOR_WIDTH > state.term_width {
+        let note = format!("Inspector needs {} columns", x + INSPECTOR_WIDTH);
+        if x + note.len() as u16 <= state.term_width {
+            queue!(
+                stdout,
+                SetBackgroundColor(Color::Reset),
+                SetForegroundColor(Color::Yellow),
+                cursor::MoveTo(x, top),
+                style::Print(note),
+            )?;
+        }
+        return Ok(());
+    }
+
+    let byte_index = get_byte_at_cursor(state, parameters);
+    let bytes = state.buffer.read(byte_index..byte_index + 16);
+
+    queue!(
+        stdout,
+        SetBackgroundColor(Color::Reset),
+        SetForegroundColor(Color::Yellow),
+        cursor::MoveTo(x, top),
+        style::Print(format!("Inspector {:#010x}", byte_index)),
+    )?;
+
+    for (row, (i, field)) in (top + 1..state.term_height).zip(FIELDS.iter().enumerate()) {
+        let (value, fg) = match editing {
+            Some((edited, input)) if edited == i => (format!("{}_", input), Color::White),
+            _ => (
+                field.format(&bytes).unwrap_or(String::from("-")),
+                Color::DarkGrey,
+            ),
+        };
+
+        queue!(
+            stdout,
+            cursor::MoveTo(x, row),
+            terminal::Clear(ClearType::UntilNewLine),
+            SetForegroundColor(Color::Yellow),
+            style::Print(format!("{:<10}", field.label())),
+            SetForegroundColor(fg),
+            style::Print(value),
+        )?;
+    }
+
+    Ok(())
+}
+
+fn draw_bytes(stdout: &mut Stdout, state: &TermState, parameters: &Parameters) -> Result<()> {
     //For each byte in file
     let mut byte_x = state.padding + 13;
     let mut byte_y = 1;
@@ -151,13 +259,19 @@ fn draw_bytes(
 
     let mut iter = 0;
     let start_from = parameters.byte_size as usize * state.render_from_offset;
+    let visible_rows = state.term_height.saturating_sub(1) as usize;
+    let end_at = start_from + visible_rows * parameters.byte_size as usize;
+
+    let bytes = state.buffer.read(start_from..end_at);
+    let spans = state.buffer.spans(start_from..end_at);
+    let selection = get_selection(state, parameters).unwrap_or_default();
+    let cursor_index = get_byte_at_cursor(state, parameters);
+    let compared = get_diff_buffer(state).map(|other| other.read(start_from..end_at));
 
     let mut fg_info: HashMap<usize, Color> = HashMap::new();
     let mut bg_info: HashMap<usize, Color> = HashMap::new();
 
-    for i in start_from..bytes.len() {
-        let byte = bytes[i];
-
+    for (i, byte) in (start_from..).zip(bytes.iter()) {
         queue!(
             stdout,
             SetBackgroundColor(Color::Reset),
@@ -167,22 +281,37 @@ fn draw_bytes(
         let mut fg = Color::DarkGrey;
         let mut bg = Color::Reset;
 
-        // Check if byte is in one of found sequences
-        //@Improvement: change to something nicer
-        for range in &state.found_sequences {
-            if range.contains(&i) {
-                fg = Color::White;
-                break;
+        if is_found(state, i) {
+            fg = Color::White;
+        }
+
+        if let Some(compared) = &compared {
+            if compared.get(i - start_from) != Some(byte) {
+                fg = DIFF_COLOR;
             }
         }
 
+        let byte_state = spans
+            .iter()
+            .find(|span| span.range.contains(&i))
+            .map(|span| span.state);
+
         //@Improvement: change to something nicer
-        if byte_y == state.row && byte_x == state.column {
+        if i == cursor_index {
             fg = Color::DarkBlue;
-        } else if state.bytes_removed.contains(&i) {
+        } else if byte_state == Some(ByteState::Removed) {
             fg = Color::Red;
-        } else if state.bytes_changed.contains(&i) {
+        } else if byte_state == Some(ByteState::Changed) {
             fg = Color::DarkBlue;
+        } else if byte_state == Some(ByteState::Inserted) {
+            fg = Color::DarkGreen;
+        }
+
+        if selection.contains(&i) {
+            bg = Color::Grey;
+            if fg == Color::DarkGrey {
+                fg = Color::Black;
+            }
         }
 
         fg_info.insert(i, fg);
@@ -199,14 +328,15 @@ fn draw_bytes(
         iter += 1;
 
         //Overflow on x axis, time to print decoded chars
-        if iter >= parameters.byte_size || i == bytes.len() - 1 {
+        if iter >= parameters.byte_size || i == start_from + bytes.len() - 1 {
             let start = i + 1 - iter as usize;
             let end = i + 1;
             let range = Range { start, end };
 
             let starting_pos = (state.dimensions.decoded.0, byte_y);
+            let row_bytes = &bytes[start - start_from..end - start_from];
 
-            draw_chars(stdout, starting_pos, range, bytes, &fg_info, &bg_info)?;
+            draw_chars(stdout, starting_pos, range, row_bytes, &fg_info, &bg_info)?;
 
             iter = 0;
             byte_x = state.padding + 13;
@@ -221,11 +351,84 @@ fn draw_bytes(
     Ok(())
 }
 
+pub(super) const DIFF_COLOR: Color = Color::Magenta;
+// Label column and the longest value, a guid
+const INSPECTOR_WIDTH: u16 = 10 + 38;
+
+// Hex and decoded columns of compared file
+// List of open files takes place of compared file in the header
+fn tabs_shown(state: &TermState) -> bool {
+    state.tabs.len() > 1 && get_diff_buffer(state).is_none()
+}
+
+fn diff_width(parameters: &Parameters) -> u16 {
+    parameters.byte_size * 5 + 2 + parameters.byte_size
+}
+
+// Compared file drawn right of decoded column, scrolled together with the active one
+fn draw_diff(stdout: &mut Stdout, state: &TermState, parameters: &Parameters) -> Result<()> {
+    let Some(other) = get_diff_buffer(state) else {
+        return Ok(());
+    };
+
+    let bytes_x = state.dimensions.decoded.1 + 3;
+    let decoded_x = bytes_x + parameters.byte_size * 5 + 2;
+    if decoded_x + parameters.byte_size > state.term_width {
+        return Ok(());
+    }
+
+    let byte_size = parameters.byte_size as usize;
+    let start_from = byte_size * state.render_from_offset;
+    let visible_rows = state.term_height.saturating_sub(1) as usize;
+    let end_at = start_from + visible_rows * byte_size;
+
+    let bytes = other.read(start_from..end_at);
+    let active = state.buffer.read(start_from..end_at);
+
+    let file_path = &state.tabs[state.diff_with.unwrap_or_default()].file_path;
+    queue!(
+        stdout,
+        SetForegroundColor(Color::Yellow),
+        SetBackgroundColor(Color::Reset),
+        cursor::MoveTo(bytes_x, 0),
+        style::Print(format!("Compared with: {}", file_path)),
+    )?;
+
+    let mut fg_info: HashMap<usize, Color> = HashMap::new();
+    let mut bg_info: HashMap<usize, Color> = HashMap::new();
+
+    for (byte_y, row) in (1..state.term_height).zip(bytes.chunks(byte_size)) {
+        let start = start_from + (byte_y as usize - 1) * byte_size;
+        queue!(stdout, cursor::MoveTo(bytes_x, byte_y))?;
+
+        for (i, byte) in (start..).zip(row) {
+            let fg = match active.get(i - start_from) != Some(byte) {
+                true => DIFF_COLOR,
+                false => Color::DarkGrey,
+            };
+
+            fg_info.insert(i, fg);
+            bg_info.insert(i, Color::Reset);
+
+            queue!(
+                stdout,
+                SetForegroundColor(fg),
+                style::Print(format!("{:#04X} ", byte))
+            )?;
+        }
+
+        let range = start..start + row.len();
+        draw_chars(stdout, (decoded_x, byte_y), range, row, &fg_info, &bg_info)?;
+    }
+
+    Ok(())
+}
+
 fn draw_chars<W: Write>(
     stdout: &mut W,
     starting_pos: (u16, u16),
     range: Range<usize>,
-    bytes: &Vec<u8>,
+    bytes: &[u8],
     fg_info: &HashMap<usize, Color>,
     bg_info: &HashMap<usize, Color>,
 ) -> Result<()> {
@@ -234,8 +437,8 @@ fn draw_chars<W: Write>(
         cursor::MoveTo(starting_pos.0, starting_pos.1),
         SetForegroundColor(Color::DarkGrey)
     )?;
-    for i in range {
-        let decoded = get_symbol(bytes[i]);
+    for (i, byte) in range.zip(bytes.iter()) {
+        let decoded = get_symbol(*byte);
 
         let fg = fg_info.get(&i).unwrap();
         let mut bg = bg_info.get(&i).unwrap();
@@ -246,7 +449,6 @@ fn draw_chars<W: Write>(
 
         queue!(
             stdout,
-            cursor::MoveRight(0),
             SetForegroundColor(*fg),
             SetBackgroundColor(*bg),
             style::Print(decoded)
@@ -256,75 +458,96 @@ fn draw_chars<W: Write>(
     Ok(())
 }
 
-fn draw_offsets(
-    stdout: &mut Stdout,
-    state: &TermState,
-    parameters: &Parameters,
-    offsets: u16,
-) -> Result<()> {
-    let mut iter = 0;
-    for i in state.render_from_offset as u16..offsets + 1 {
-        if iter >= state.term_height - 1 {
-            break;
-        }
+fn draw_offsets(stdout: &mut Stdout, state: &TermState, parameters: &Parameters) -> Result<()> {
+    let offsets = state.buffer.len() / parameters.byte_size as usize;
+    for (iter, i) in (1..state.term_height).zip(state.render_from_offset..offsets + 1) {
         queue!(
             stdout,
             style::SetForegroundColor(Color::Yellow),
             style::SetBackgroundColor(Color::Reset),
-            cursor::MoveTo(state.padding, iter + 1 as u16),
-            style::Print(format!("{:#010x}", i * parameters.byte_size))
+            cursor::MoveTo(state.dimensions.offsets.0, iter),
+            style::Print(format!("{:#010x}", i * parameters.byte_size as usize))
         )?;
-        iter += 1;
     }
     Ok(())
 }
 
-fn get_status(state: &TermState, parameters: &Parameters, keyboard: &Keyboard) -> String {
-    match state.status_mode {
+fn get_status(state: &TermState, parameters: &Parameters) -> String {
+    match &state.status_mode {
         StatusMode::General => {
             let mut status = format!(
                 "Hex Editor ({}x{}) - {}:{}, file: {}",
-                state.term_width, state.term_height, state.column, state.row, &parameters.file_path
+                state.term_width, state.term_height, state.column, state.row, &state.file_path
             );
 
-            if state.bytes_changed.len() > 0 {
-                let bytes_info = format!(", Bytes changes: {}", state.bytes_changed.len());
+            if state.readonly {
+                status.push_str(" [read-only]");
+            }
+
+            if state.follow {
+                status.push_str(" [following]");
+            }
+
+            let changed = state.buffer.count(ByteState::Changed);
+            if changed > 0 {
+                let bytes_info = format!(", Bytes changes: {}", changed);
                 status.push_str(&bytes_info);
             }
 
-            status
-        }
-    }
-}
+            let inserted = state.buffer.count(ByteState::Inserted);
+            if inserted > 0 {
+                let bytes_info = format!(", Bytes inserted: {}", inserted);
+                status.push_str(&bytes_info);
+            }
 
-fn get_symbol(byte: u8) -> char {
-    if byte.is_ascii_whitespace() {
-        return ' ';
-    }
+            if state.register != DEFAULT_REGISTER {
+                let register_info = format!(", Register: {}", state.register);
+                status.push_str(&register_info);
+            }
 
-    if !byte.is_ascii() || byte.is_ascii_control() {
-        return '.';
-    }
+            if let Some(selection) = get_selection(state, parameters) {
+                let bytes_info = format!(", Selected: {}", selection.len());
+                status.push_str(&bytes_info);
+            }
 
-    char::from(byte)
+            if let Some(search) = &state.search {
+                let search_info = format!(
+                    ", Searching… {}% ({} found, esc to stop)",
+                    search.percent(),
+                    search.found
+                );
+                status.push_str(&search_info);
+            } else if !state.found_sequences.is_empty() {
+                let found_info = format!(", Found: {}", state.found_sequences.len());
+                status.push_str(&found_info);
+            }
+
+            status
+        }
+        StatusMode::Error(error) => error.clone(),
+    }
 }
 
 fn draw_fixed_ui<W: Write>(
     stdout: &mut W,
     state: &TermState,
     parameters: &Parameters,
-    keyboard: &Keyboard,
 ) -> Result<()> {
-    let status = get_status(state, parameters, keyboard);
+    let status = get_status(state, parameters);
+    let status_color = match state.status_mode {
+        StatusMode::Error(_) => Color::Red,
+        StatusMode::General => Color::Yellow,
+    };
 
     queue!(
         stdout,
-        style::SetForegroundColor(Color::Yellow),
+        style::SetForegroundColor(status_color),
         style::SetBackgroundColor(Color::Reset),
-        cursor::MoveTo(state.padding, 0),
-        style::Print("Offset(h)"),
         cursor::MoveTo(state.padding, state.term_height),
         style::Print(status),
+        style::SetForegroundColor(Color::Yellow),
+        cursor::MoveTo(state.padding, 0),
+        style::Print("Offset(h)"),
         cursor::MoveTo(state.padding + 12, 0),
     )?;
 
@@ -338,5 +561,30 @@ fn draw_fixed_ui<W: Write>(
     }
     //Decoded
     queue!(stdout, cursor::MoveRight(3), style::Print("Decoded"))?;
+
+    //Open files, active one highlighted and modified ones marked with '*'
+    if tabs_shown(state) {
+        queue!(stdout, cursor::MoveRight(3))?;
+        for (i, (file_path, buffer)) in open_files(state).into_iter().enumerate() {
+            let name = Path::new(file_path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| file_path.to_string());
+            let modified = if buffer.is_modified() { "*" } else { "" };
+
+            let (fg, bg) = match i == state.active_tab {
+                true => (Color::Black, Color::Yellow),
+                false => (Color::Yellow, Color::Reset),
+            };
+
+            queue!(
+                stdout,
+                style::SetForegroundColor(fg),
+                style::SetBackgroundColor(bg),
+                style::Print(format!(" {}:{}{} ", i + 1, name, modified)),
+            )?;
+        }
+        queue!(stdout, style::SetBackgroundColor(Color::Reset))?;
+    }
     Ok(())
 }
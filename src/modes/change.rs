@@ -1,16 +1,26 @@
+use crate::{
+    buffer::EditBuffer,
+    misc::{
+        get_byte_at_cursor, get_selection_or_cursor, is_cursor_in_decoded, put_cursor_at_index,
+        Parameters, TermState,
+    },
+};
 use crossterm::{
     cursor,
     event::KeyCode,
-    queue, style,
+    queue,
+    style::{self, Color},
+    terminal::{self, ClearType},
 };
 use std::io::Result;
-use crate::misc::{get_byte_at_cursor, Parameters, TermState};
 
-use super::{Mode, Modes};
+use super::{bytes::draw_view, Mode, Modes};
 
 pub struct ChangeMode<'a> {
     pub input: String,
     parameters: &'a Parameters,
+    // Text typed in one go is undone together
+    step_started: bool,
 }
 
 impl<'a> ChangeMode<'a> {
@@ -18,6 +28,37 @@ impl<'a> ChangeMode<'a> {
         Self {
             input: String::from(""),
             parameters,
+            step_started: false,
+        }
+    }
+
+    // Typing in decoded column overwrites bytes with typed text right away
+    fn handle_text(&mut self, code: KeyCode, state: &mut TermState) -> Modes {
+        match code {
+            KeyCode::Esc | KeyCode::Enter => {
+                self.step_started = false;
+                Modes::Bytes
+            }
+            KeyCode::Char(char) if !char.is_control() => {
+                let byte_index = get_byte_at_cursor(state, self.parameters);
+                if byte_index >= state.buffer.len() {
+                    return Modes::Change;
+                }
+
+                let mut encoded = [0; 4];
+                let bytes = char.encode_utf8(&mut encoded).as_bytes();
+                let bytes = &bytes[..bytes.len().min(state.buffer.len() - byte_index)];
+
+                if !self.step_started {
+                    state.buffer.checkpoint(byte_index);
+                    self.step_started = true;
+                }
+                state.buffer.overwrite(byte_index, bytes);
+
+                put_cursor_at_index(state, byte_index + bytes.len(), self.parameters);
+                Modes::Change
+            }
+            _ => Modes::Change,
         }
     }
 }
@@ -27,8 +68,12 @@ impl<'a> Mode for ChangeMode<'a> {
         &mut self,
         event: &crossterm::event::KeyEvent,
         state: &mut TermState,
-        parameters: &Parameters,
+        _parameters: &Parameters,
     ) -> Result<super::Modes> {
+        if is_cursor_in_decoded(state) {
+            return Ok(self.handle_text(event.code, state));
+        }
+
         let end_mode = match event.code {
             KeyCode::Char(char) => {
                 if char == 'q' {
@@ -53,17 +98,13 @@ impl<'a> Mode for ChangeMode<'a> {
                 let byte =
                     u8::from_str_radix(&self.input, 16).expect("Failed to convert input to byte");
 
-                let byte_index = get_byte_at_cursor(state, self.parameters);
-
-                if state.bytes_removed.contains(&byte_index) {
-                    state.bytes_removed.remove(&byte_index);
-                }
-
-                if !state.bytes_changed.contains(&byte_index) {
-                    state.bytes_changed.insert(byte_index);
-                }
-
-                state.bytes[byte_index] = byte;
+                // With selection active whole selection is filled with given byte
+                let range = get_selection_or_cursor(state, self.parameters);
+                state.buffer.checkpoint(range.start);
+                state
+                    .buffer
+                    .overwrite(range.start, &vec![byte; range.len()]);
+                state.selection_anchor = None;
 
                 self.input.clear();
 
@@ -77,25 +118,47 @@ impl<'a> Mode for ChangeMode<'a> {
 
     fn handle_mouse(
         &mut self,
-        event: &crossterm::event::MouseEvent,
-        state: &mut TermState,
-        parameters: &Parameters,
+        _event: &crossterm::event::MouseEvent,
+        _state: &mut TermState,
+        _parameters: &Parameters,
     ) -> Result<super::Modes> {
         Ok(Modes::Change)
     }
 
     fn handle_resize(
         &mut self,
-        stdout: &mut std::io::Stdout,
-        width: u16,
-        height: u16,
-        state: &mut TermState,
-        parameters: &Parameters,
+        _stdout: &mut std::io::Stdout,
+        _width: u16,
+        _height: u16,
+        _state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        Ok(Modes::Change)
+    }
+
+    fn handle_paste(
+        &mut self,
+        _text: &str,
+        _state: &mut TermState,
+        _parameters: &Parameters,
     ) -> Result<super::Modes> {
         Ok(Modes::Change)
     }
 
     fn draw(&self, stdout: &mut std::io::Stdout, state: &TermState) -> Result<()> {
+        if is_cursor_in_decoded(state) {
+            draw_view(stdout, state, self.parameters)?;
+            queue!(
+                stdout,
+                cursor::MoveTo(1, state.term_height),
+                terminal::Clear(ClearType::FromCursorDown),
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print("-- TEXT --"),
+                cursor::MoveTo(state.column, state.row),
+            )?;
+            return Ok(());
+        }
+
         for i in 1..4 {
             queue!(
                 stdout,
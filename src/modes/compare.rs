@@ -0,0 +1,251 @@
+use crossterm::{
+    cursor,
+    event::{self, KeyCode},
+    queue,
+    style::{self, Color, SetBackgroundColor, SetForegroundColor},
+    terminal::{self, ClearType},
+};
+
+use std::io::{Result, Stdout};
+
+use crate::{
+    align::{HunkKind, Row},
+    buffer::{EditBuffer, PieceTable},
+    misc::{get_symbol, put_cursor_at_index, Direction, Parameters, TermState},
+};
+
+use super::{bytes::DIFF_COLOR, Mode, Modes};
+
+// Aligned view of two files, inserted and deleted blocks are padded with gaps
+pub struct CompareMode<'a> {
+    parameters: &'a Parameters,
+    top: usize,
+}
+
+impl<'a> CompareMode<'a> {
+    pub fn new(parameters: &'a Parameters) -> Self {
+        Self { parameters, top: 0 }
+    }
+
+    fn finish(&mut self, state: &mut TermState) -> Modes {
+        state.comparison = None;
+        self.top = 0;
+        Modes::Bytes
+    }
+
+    fn scroll_to(&mut self, state: &TermState, row: usize) {
+        let rows = state
+            .comparison
+            .as_ref()
+            .map_or(0, |comparison| comparison.rows());
+        self.top = row.min(rows.saturating_sub(1));
+    }
+
+    // Active file is shown from where the top row of aligned view is
+    fn go_to(&mut self, state: &mut TermState) -> Modes {
+        let at = state
+            .comparison
+            .as_ref()
+            .and_then(|comparison| comparison.at_row(self.top))
+            .map(|row| row.left.start);
+
+        if let Some(at) = at {
+            put_cursor_at_index(
+                state,
+                at.min(state.buffer.len().saturating_sub(1)),
+                self.parameters,
+            );
+        }
+
+        self.finish(state)
+    }
+
+    fn draw_side(
+        &self,
+        stdout: &mut Stdout,
+        x: u16,
+        y: u16,
+        row: &Row,
+        buffer: &PieceTable,
+        left: bool,
+    ) -> Result<()> {
+        let range = match left {
+            true => &row.left,
+            false => &row.right,
+        };
+
+        if !range.is_empty() {
+            queue!(
+                stdout,
+                SetForegroundColor(Color::Yellow),
+                cursor::MoveTo(x, y),
+                style::Print(format!("{:#010x}", range.start)),
+            )?;
+        }
+
+        let bytes_x = x + 13;
+        let decoded_x = bytes_x + self.parameters.byte_size * 5 + 2;
+        let bytes = buffer.read(range.clone());
+
+        for (column, kind) in row.kinds.iter().enumerate() {
+            let (hex, decoded) = match bytes.get(column) {
+                Some(byte) => (format!("{:#04X}", byte), get_symbol(*byte)),
+                None => (String::from("----"), ' '),
+            };
+            let fg = match kind {
+                HunkKind::Same => Color::DarkGrey,
+                HunkKind::Changed => DIFF_COLOR,
+                HunkKind::Deleted => Color::Red,
+                HunkKind::Inserted => Color::DarkGreen,
+            };
+
+            let column = column as u16;
+            queue!(
+                stdout,
+                SetForegroundColor(fg),
+                cursor::MoveTo(bytes_x + column * 5, y),
+                style::Print(hex),
+                cursor::MoveTo(decoded_x + column, y),
+                style::Print(decoded),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Mode for CompareMode<'a> {
+    fn handle_input(
+        &mut self,
+        event: &crossterm::event::KeyEvent,
+        state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        let page = state.term_height.saturating_sub(2) as usize;
+
+        match event.code {
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(self.finish(state)),
+            KeyCode::Enter => return Ok(self.go_to(state)),
+            KeyCode::Up => self.top = self.top.saturating_sub(1),
+            KeyCode::Down => self.scroll_to(state, self.top + 1),
+            KeyCode::PageUp => self.top = self.top.saturating_sub(page),
+            KeyCode::PageDown => self.scroll_to(state, self.top + page),
+            KeyCode::Home => self.top = 0,
+            KeyCode::End => self.scroll_to(state, usize::MAX),
+            KeyCode::Char('n') | KeyCode::Char('p') => {
+                let direction = match event.code {
+                    KeyCode::Char('n') => Direction::Right,
+                    _ => Direction::Left,
+                };
+                let row = state
+                    .comparison
+                    .as_ref()
+                    .and_then(|comparison| comparison.closest_difference(self.top, direction));
+                if let Some(row) = row {
+                    self.top = row;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(Modes::Compare)
+    }
+
+    fn handle_mouse(
+        &mut self,
+        event: &crossterm::event::MouseEvent,
+        state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        match event.kind {
+            event::MouseEventKind::ScrollDown => self.scroll_to(state, self.top + 1),
+            event::MouseEventKind::ScrollUp => self.top = self.top.saturating_sub(1),
+            _ => {}
+        }
+        Ok(Modes::Compare)
+    }
+
+    fn handle_resize(
+        &mut self,
+        _stdout: &mut Stdout,
+        width: u16,
+        height: u16,
+        state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        state.term_width = width;
+        state.term_height = height;
+        Ok(Modes::Compare)
+    }
+
+    fn handle_paste(
+        &mut self,
+        _text: &str,
+        _state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        Ok(Modes::Compare)
+    }
+
+    fn draw(&self, stdout: &mut Stdout, state: &TermState) -> Result<()> {
+        queue!(
+            stdout,
+            terminal::Clear(ClearType::All),
+            SetBackgroundColor(Color::Reset)
+        )?;
+
+        let Some(comparison) = &state.comparison else {
+            return Ok(());
+        };
+
+        let left_x = state.dimensions.offsets.0;
+        let right_x = state.dimensions.decoded.1 + 3;
+        let needed = right_x + 13 + self.parameters.byte_size * 6 + 2;
+        if state.term_width < needed {
+            queue!(
+                stdout,
+                cursor::MoveTo(1, 1),
+                style::Print(format!(
+                    "Window needs {} columns to compare files, esc to close",
+                    needed
+                ))
+            )?;
+            return Ok(());
+        }
+
+        let other = &state.tabs[comparison.with];
+
+        queue!(
+            stdout,
+            SetForegroundColor(Color::Yellow),
+            cursor::MoveTo(left_x, 0),
+            style::Print(format!("Offset(h)    {}", state.file_path)),
+            cursor::MoveTo(right_x, 0),
+            style::Print(format!("Offset(h)    {}", other.file_path)),
+        )?;
+
+        for y in 1..state.term_height {
+            let Some(row) = comparison.at_row(self.top + y as usize - 1) else {
+                break;
+            };
+            self.draw_side(stdout, left_x, y, &row, &state.buffer, true)?;
+            self.draw_side(stdout, right_x, y, &row, &other.buffer, false)?;
+        }
+
+        let status = format!(
+            "{} changed, {} inserted, {} deleted bytes - n/p: next/previous difference, enter: go to, esc: close",
+            comparison.count(HunkKind::Changed),
+            comparison.count(HunkKind::Inserted),
+            comparison.count(HunkKind::Deleted),
+        );
+        queue!(
+            stdout,
+            cursor::MoveTo(1, state.term_height),
+            terminal::Clear(ClearType::FromCursorDown),
+            SetForegroundColor(Color::Yellow),
+            style::Print(status),
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use crossterm::{
+    cursor,
+    event::KeyCode,
+    queue,
+    style::{self, Color},
+    terminal::{self, ClearType},
+};
+
+use std::io::Result;
+
+use crate::{
+    actions::save_buffer,
+    buffer::{ByteState, EditBuffer},
+    misc::{is_changed_on_disk, open_files, switch_tab, StatusMode},
+};
+
+use super::{Mode, Modes};
+
+// Asks what to do with unsaved changes before leaving the file
+pub struct ConfirmMode {}
+
+impl ConfirmMode {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Mode for ConfirmMode {
+    fn handle_input(
+        &mut self,
+        event: &crossterm::event::KeyEvent,
+        state: &mut crate::misc::TermState,
+        parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        match event.code {
+            KeyCode::Char('s') | KeyCode::Char('y') => {
+                // Every modified file is saved, the one which could not be is left shown
+                let active_tab = state.active_tab;
+                for i in 0..state.tabs.len() {
+                    switch_tab(state, i);
+                    if !state.buffer.is_modified() {
+                        continue;
+                    }
+                    if is_changed_on_disk(state) {
+                        return Ok(Modes::External);
+                    }
+                    if let Err(error) = save_buffer(state, parameters) {
+                        state.status_mode = StatusMode::Error(format!("Failed to save: {}", error));
+                        return Ok(Modes::Bytes);
+                    }
+                }
+                switch_tab(state, active_tab);
+
+                Ok(state.after_confirm)
+            }
+            KeyCode::Char('d') | KeyCode::Char('n') => Ok(state.after_confirm),
+            KeyCode::Char('c') | KeyCode::Esc => Ok(Modes::Bytes),
+            _ => Ok(Modes::Confirm),
+        }
+    }
+
+    fn handle_mouse(
+        &mut self,
+        _event: &crossterm::event::MouseEvent,
+        _state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        Ok(Modes::Confirm)
+    }
+
+    fn handle_resize(
+        &mut self,
+        _stdout: &mut std::io::Stdout,
+        width: u16,
+        height: u16,
+        state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        state.term_width = width;
+        state.term_height = height;
+        Ok(Modes::Confirm)
+    }
+
+    fn handle_paste(
+        &mut self,
+        _text: &str,
+        _state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        Ok(Modes::Confirm)
+    }
+
+    fn draw(&self, stdout: &mut std::io::Stdout, state: &crate::misc::TermState) -> Result<()> {
+        let files = open_files(state);
+        let count = |byte_state: ByteState| -> usize {
+            files
+                .iter()
+                .map(|(_, buffer)| buffer.count(byte_state))
+                .sum()
+        };
+        let modified_files = files
+            .iter()
+            .filter(|(_, buffer)| buffer.is_modified())
+            .count();
+
+        let msg = format!(
+            "Unsaved changes in {} file(s) ({} modified, {} inserted, {} removed): (s)ave, (d)iscard, (c)ancel",
+            modified_files,
+            count(ByteState::Changed),
+            count(ByteState::Inserted),
+            count(ByteState::Removed),
+        );
+
+        queue!(
+            stdout,
+            cursor::MoveTo(1, state.term_height),
+            terminal::Clear(ClearType::FromCursorDown),
+            style::SetForegroundColor(Color::Yellow),
+            style::Print(msg),
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+use crossterm::{
+    cursor::{self, SetCursorStyle},
+    event::KeyCode,
+    queue,
+    style::{self, Color},
+    terminal::{self, ClearType},
+};
+
+use std::{fs, io::Result};
+
+use crate::{buffer::EditBuffer, misc::get_selection_or_cursor};
+
+use super::{Mode, Modes};
+
+pub struct ExportMode {
+    input: String,
+    error: Option<String>,
+}
+
+impl ExportMode {
+    pub fn new() -> Self {
+        Self {
+            input: String::from(""),
+            error: None,
+        }
+    }
+}
+
+impl Mode for ExportMode {
+    fn handle_input(
+        &mut self,
+        event: &crossterm::event::KeyEvent,
+        state: &mut crate::misc::TermState,
+        parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        self.error = None;
+
+        let end_mode = match event.code {
+            KeyCode::Esc => Modes::Bytes,
+            KeyCode::Backspace => {
+                self.input.pop();
+                Modes::Export
+            }
+            KeyCode::Char(char) => {
+                self.input.push(char);
+                Modes::Export
+            }
+            KeyCode::Enter => {
+                if self.input.is_empty() {
+                    return Ok(Modes::Export);
+                }
+
+                let range = get_selection_or_cursor(state, parameters);
+                let bytes = state.buffer.read(range);
+
+                if let Err(error) = fs::write(&self.input, bytes) {
+                    self.error = Some(format!("Failed to export: {}", error));
+                    return Ok(Modes::Export);
+                }
+
+                state.selection_anchor = None;
+                self.input.clear();
+
+                Modes::Bytes
+            }
+            _ => Modes::Export,
+        };
+
+        Ok(end_mode)
+    }
+
+    fn handle_mouse(
+        &mut self,
+        _event: &crossterm::event::MouseEvent,
+        _state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        Ok(Modes::Export)
+    }
+
+    fn handle_resize(
+        &mut self,
+        _stdout: &mut std::io::Stdout,
+        _width: u16,
+        _height: u16,
+        _state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        Ok(Modes::Export)
+    }
+
+    fn handle_paste(
+        &mut self,
+        text: &str,
+        _state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        self.input.push_str(text.trim());
+        Ok(Modes::Export)
+    }
+
+    fn draw(&self, stdout: &mut std::io::Stdout, state: &crate::misc::TermState) -> Result<()> {
+        let msg = format!("Export to: {}", self.input);
+
+        if let Some(error) = &self.error {
+            queue!(
+                stdout,
+                cursor::MoveTo(1, state.term_height),
+                terminal::Clear(ClearType::FromCursorDown),
+                style::SetForegroundColor(Color::Red),
+                style::Print(error),
+            )?;
+        } else {
+            queue!(
+                stdout,
+                cursor::MoveTo(1, state.term_height),
+                terminal::Clear(ClearType::FromCursorDown),
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print(&msg),
+                SetCursorStyle::SteadyBlock,
+                cursor::MoveTo(1 + msg.len() as u16, state.term_height),
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+use crossterm::{
+    cursor,
+    event::KeyCode,
+    queue,
+    style::{self, Color},
+    terminal::{self, ClearType},
+};
+
+use std::{
+    fs::File,
+    io::{Read, Result},
+};
+
+use crate::{
+    actions::{is_editable, open_buffer, save_buffer},
+    buffer::EditBuffer,
+    misc::{get_byte_at_cursor, put_cursor_at_index, FileStamp, Parameters, StatusMode, TermState},
+    search_job,
+};
+
+use super::{Mode, Modes};
+
+const COMPARE_CHUNK: usize = 1 << 20;
+
+// Shown when another program wrote to the file after it was loaded
+pub struct ExternalMode {
+    diff: Option<String>,
+    error: Option<String>,
+    // Reload was chosen while there are unsaved changes
+    confirm_reload: bool,
+}
+
+impl ExternalMode {
+    pub fn new() -> Self {
+        Self {
+            diff: None,
+            error: None,
+            confirm_reload: false,
+        }
+    }
+
+    fn finish(&mut self) -> Modes {
+        self.diff = None;
+        self.confirm_reload = false;
+        Modes::Bytes
+    }
+
+    fn reload(&mut self, state: &mut TermState, parameters: &Parameters) -> Modes {
+        let path = state.file_path.clone();
+        if let Err(error) = open_buffer(state, &path) {
+            self.error = Some(format!("Failed to reload: {}", error));
+            self.confirm_reload = false;
+            return Modes::External;
+        }
+
+        state.selection_anchor = None;
+        search_job::cancel(state);
+        state.found_sequences.clear();
+        state.status_mode = StatusMode::General;
+
+        let byte_index = get_byte_at_cursor(state, parameters);
+        put_cursor_at_index(
+            state,
+            byte_index.min(state.buffer.len().saturating_sub(1)),
+            parameters,
+        );
+
+        self.finish()
+    }
+}
+
+// Compares file as it was loaded with what is on disk now, chunk by chunk so that
+// big files are not read into memory
+fn describe_difference(state: &TermState) -> std::io::Result<String> {
+    let mut on_disk = File::open(&state.file_path)?;
+    let on_disk_len = on_disk.metadata()?.len() as usize;
+    let loaded_len = state.buffer.original_len();
+    let common = loaded_len.min(on_disk_len);
+
+    let mut differing = 0;
+    let mut first = None;
+    let mut chunk = vec![];
+    for start in (0..common).step_by(COMPARE_CHUNK) {
+        let end = (start + COMPARE_CHUNK).min(common);
+        let loaded = state.buffer.read_original(start..end)?;
+        chunk.resize(end - start, 0);
+        on_disk.read_exact(&mut chunk)?;
+
+        for (i, _) in loaded
+            .iter()
+            .zip(&chunk)
+            .enumerate()
+            .filter(|(_, (loaded, on_disk))| loaded != on_disk)
+        {
+            differing += 1;
+            first.get_or_insert(start + i);
+        }
+    }
+    let first = first.or((loaded_len != on_disk_len).then_some(common));
+
+    let description = match first {
+        Some(first) => format!(
+            "{} bytes differ, first at {:#010X}, size {} -> {}",
+            differing, first, loaded_len, on_disk_len
+        ),
+        None => String::from("Content on disk is the same as loaded one"),
+    };
+
+    Ok(description)
+}
+
+// Loaded file was cut short in place, so its bytes past the new end can't be read anymore
+fn truncated_to(state: &TermState) -> Option<u64> {
+    let on_disk = FileStamp::of(&state.file_path)?;
+    let loaded = state.stamp.as_ref()?;
+    let truncated =
+        on_disk.is_same_file(loaded) && on_disk.size() < state.buffer.original_len() as u64;
+    truncated.then_some(on_disk.size())
+}
+
+impl Mode for ExternalMode {
+    fn handle_input(
+        &mut self,
+        event: &crossterm::event::KeyEvent,
+        state: &mut TermState,
+        parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        self.error = None;
+
+        if self.confirm_reload {
+            let end_mode = match event.code {
+                KeyCode::Char('y') => self.reload(state, parameters),
+                KeyCode::Char('n') | KeyCode::Esc => {
+                    self.confirm_reload = false;
+                    Modes::External
+                }
+                _ => Modes::External,
+            };
+            return Ok(end_mode);
+        }
+
+        let end_mode = match event.code {
+            KeyCode::Char('r') => {
+                if state.buffer.is_modified() {
+                    self.confirm_reload = true;
+                    return Ok(Modes::External);
+                }
+                self.reload(state, parameters)
+            }
+            KeyCode::Char('d') => {
+                match describe_difference(state) {
+                    Ok(diff) => self.diff = Some(diff),
+                    Err(error) => self.error = Some(format!("Failed to compare: {}", error)),
+                }
+                Modes::External
+            }
+            KeyCode::Char('o') => {
+                if !is_editable(state) {
+                    return Ok(self.finish());
+                }
+                if let Err(error) = save_buffer(state, parameters) {
+                    self.error = Some(format!("Failed to save: {}", error));
+                    return Ok(Modes::External);
+                }
+                state.status_mode = StatusMode::General;
+                self.finish()
+            }
+            // Keep editing, the change on disk is acknowledged and not reported again
+            KeyCode::Char('i') | KeyCode::Esc => {
+                state.stamp = FileStamp::of(&state.file_path);
+                self.finish()
+            }
+            _ => Modes::External,
+        };
+
+        Ok(end_mode)
+    }
+
+    fn handle_mouse(
+        &mut self,
+        _event: &crossterm::event::MouseEvent,
+        _state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        Ok(Modes::External)
+    }
+
+    fn handle_resize(
+        &mut self,
+        _stdout: &mut std::io::Stdout,
+        width: u16,
+        height: u16,
+        state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        state.term_width = width;
+        state.term_height = height;
+        Ok(Modes::External)
+    }
+
+    fn handle_paste(
+        &mut self,
+        _text: &str,
+        _state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        Ok(Modes::External)
+    }
+
+    fn draw(&self, stdout: &mut std::io::Stdout, state: &TermState) -> Result<()> {
+        let (msg, fg) = if let Some(error) = &self.error {
+            (error.clone(), Color::Red)
+        } else if self.confirm_reload {
+            (
+                String::from("Reload and lose unsaved changes? (y/n)"),
+                Color::Yellow,
+            )
+        } else {
+            let options = "(r)eload, (d)iff, (o)verwrite, (i)gnore";
+            match (&self.diff, truncated_to(state)) {
+                (Some(diff), _) => (format!("{}: {}", diff, options), Color::Yellow),
+                (None, Some(size)) => (
+                    format!(
+                        "File was truncated on disk, bytes past {:#010X} show as zeros: {}",
+                        size, options
+                    ),
+                    Color::Red,
+                ),
+                (None, None) if state.buffer.is_original_rewritten() => (
+                    format!(
+                        "File was rewritten in place, shown bytes mix old and new content and can't be saved: {}",
+                        options
+                    ),
+                    Color::Red,
+                ),
+                (None, None) => (
+                    format!("File was changed on disk: {}", options),
+                    Color::Yellow,
+                ),
+            }
+        };
+
+        queue!(
+            stdout,
+            cursor::MoveTo(1, state.term_height),
+            terminal::Clear(ClearType::FromCursorDown),
+            style::SetForegroundColor(fg),
+            style::Print(msg),
+        )?;
+
+        Ok(())
+    }
+}
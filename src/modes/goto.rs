@@ -8,6 +8,8 @@ use crossterm::{
 
 use std::io::Result;
 
+use crate::buffer::EditBuffer;
+
 use super::{Mode, Modes};
 
 pub struct GoToMode {
@@ -65,7 +67,7 @@ impl Mode for GoToMode {
                 Modes::GoTo
             }
             KeyCode::Enter => {
-                let total_number_of_offsets = state.bytes.len() / parameters.byte_size as usize;
+                let total_number_of_offsets = state.buffer.len() / parameters.byte_size as usize;
                 let number = usize::from_str_radix(&self.input, 16)
                     .expect("Failed to parse offset as usize");
 
@@ -88,20 +90,29 @@ impl Mode for GoToMode {
 
     fn handle_mouse(
         &mut self,
-        event: &crossterm::event::MouseEvent,
-        state: &mut crate::misc::TermState,
-        parameters: &crate::misc::Parameters,
+        _event: &crossterm::event::MouseEvent,
+        _state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
     ) -> Result<super::Modes> {
         Ok(Modes::GoTo)
     }
 
     fn handle_resize(
         &mut self,
-        stdout: &mut std::io::Stdout,
-        width: u16,
-        height: u16,
-        state: &mut crate::misc::TermState,
-        parameters: &crate::misc::Parameters,
+        _stdout: &mut std::io::Stdout,
+        _width: u16,
+        _height: u16,
+        _state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        Ok(Modes::GoTo)
+    }
+
+    fn handle_paste(
+        &mut self,
+        _text: &str,
+        _state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
     ) -> Result<super::Modes> {
         Ok(Modes::GoTo)
     }
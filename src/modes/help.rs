@@ -27,41 +27,49 @@ impl<'a> HelpMode<'a> {
 impl<'a> Mode for HelpMode<'a> {
     fn handle_input(
         &mut self,
-        event: &KeyEvent,
-        state: &mut TermState,
-        parameters: &Parameters,
+        _event: &KeyEvent,
+        _state: &mut TermState,
+        _parameters: &Parameters,
     ) -> Result<Modes> {
         Ok(Modes::Bytes)
     }
 
     fn handle_mouse(
         &mut self,
-        event: &MouseEvent,
-        state: &mut TermState,
-        parameters: &Parameters,
+        _event: &MouseEvent,
+        _state: &mut TermState,
+        _parameters: &Parameters,
     ) -> Result<Modes> {
         Ok(Modes::Help)
     }
 
     fn handle_resize(
         &mut self,
-        stdout: &mut Stdout,
-        width: u16,
-        height: u16,
-        state: &mut TermState,
-        parameters: &Parameters,
+        _stdout: &mut Stdout,
+        _width: u16,
+        _height: u16,
+        _state: &mut TermState,
+        _parameters: &Parameters,
     ) -> Result<Modes> {
         Ok(Modes::Help)
     }
 
-    fn draw(&self, stdout: &mut Stdout, state: &TermState) -> Result<()> {
+    fn handle_paste(
+        &mut self,
+        _text: &str,
+        _state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<Modes> {
+        Ok(Modes::Help)
+    }
+
+    fn draw(&self, stdout: &mut Stdout, _state: &TermState) -> Result<()> {
         let help_text = self.keyboard.help("\n");
         let help_items = help_text.lines();
 
-        let mut i = self.padding;
         queue!(stdout, terminal::Clear(ClearType::All))?;
 
-        for line in help_items {
+        for (i, line) in (self.padding..).zip(help_items) {
             let splited: Vec<&str> = line.split(": ").collect();
             let move_by = (20 - splited[0].len()) as u16;
             queue!(
@@ -71,7 +79,6 @@ impl<'a> Mode for HelpMode<'a> {
                 cursor::MoveRight(move_by),
                 style::Print(splited[1])
             )?;
-            i += 1;
         }
 
         Ok(())
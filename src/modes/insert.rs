@@ -0,0 +1,142 @@
+use crate::{
+    buffer::EditBuffer,
+    misc::{get_byte_at_cursor, is_cursor_in_decoded, put_cursor_at_index, Parameters, TermState},
+};
+use crossterm::{
+    cursor,
+    event::KeyCode,
+    queue,
+    style::{self, Color},
+    terminal::{self, ClearType},
+};
+use std::io::Result;
+
+use super::{bytes::draw_view, Mode, Modes};
+
+pub struct InsertMode<'a> {
+    input: String,
+    parameters: &'a Parameters,
+    // Bytes typed in one go are undone together
+    step_started: bool,
+}
+
+impl<'a> InsertMode<'a> {
+    pub fn new(parameters: &'a Parameters) -> Self {
+        Self {
+            input: String::from(""),
+            parameters,
+            step_started: false,
+        }
+    }
+
+    fn insert_bytes(&mut self, state: &mut TermState, bytes: &[u8]) {
+        let byte_index = get_byte_at_cursor(state, self.parameters);
+        let byte_index = byte_index.min(state.buffer.len());
+        if !self.step_started {
+            state.buffer.checkpoint(byte_index);
+            self.step_started = true;
+        }
+        state.buffer.insert(byte_index, bytes);
+
+        put_cursor_at_index(state, byte_index + bytes.len(), self.parameters);
+    }
+}
+
+impl<'a> Mode for InsertMode<'a> {
+    fn handle_input(
+        &mut self,
+        event: &crossterm::event::KeyEvent,
+        state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        let end_mode = match event.code {
+            // In decoded column typed text is inserted as is
+            KeyCode::Char(char) if is_cursor_in_decoded(state) && !char.is_control() => {
+                let mut encoded = [0; 4];
+                self.insert_bytes(state, char.encode_utf8(&mut encoded).as_bytes());
+                Modes::Insert
+            }
+            KeyCode::Insert | KeyCode::Esc | KeyCode::Char('q') => {
+                self.input.clear();
+                self.step_started = false;
+                Modes::Bytes
+            }
+            KeyCode::Char(char) => {
+                if !char.is_ascii_hexdigit() {
+                    return Ok(Modes::Insert);
+                }
+                self.input.push(char.to_ascii_uppercase());
+
+                if self.input.len() == 2 {
+                    let byte = u8::from_str_radix(&self.input, 16)
+                        .expect("Failed to convert input to byte");
+
+                    self.insert_bytes(state, &[byte]);
+                    self.input.clear();
+                }
+
+                Modes::Insert
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+                Modes::Insert
+            }
+            _ => Modes::Insert,
+        };
+
+        Ok(end_mode)
+    }
+
+    fn handle_mouse(
+        &mut self,
+        _event: &crossterm::event::MouseEvent,
+        _state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        Ok(Modes::Insert)
+    }
+
+    fn handle_resize(
+        &mut self,
+        _stdout: &mut std::io::Stdout,
+        width: u16,
+        height: u16,
+        state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        state.term_width = width;
+        state.term_height = height;
+        Ok(Modes::Insert)
+    }
+
+    fn handle_paste(
+        &mut self,
+        text: &str,
+        state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        // Paste is an undo step of its own, typing after it starts a new one
+        self.input.clear();
+        self.step_started = false;
+
+        state.pasted = text.to_string();
+        Ok(Modes::Paste)
+    }
+
+    fn draw(&self, stdout: &mut std::io::Stdout, state: &TermState) -> Result<()> {
+        draw_view(stdout, state, self.parameters)?;
+
+        queue!(
+            stdout,
+            cursor::MoveTo(1, state.term_height),
+            terminal::Clear(ClearType::FromCursorDown),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(match is_cursor_in_decoded(state) {
+                true => String::from("-- INSERT TEXT --"),
+                false => format!("-- INSERT -- 0x{}", &self.input),
+            }),
+            cursor::MoveTo(state.column, state.row),
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+use crossterm::{
+    cursor,
+    event::KeyCode,
+    queue,
+    style::{self, Color},
+    terminal::{self, ClearType},
+};
+
+use std::io::Result;
+
+use crate::{
+    buffer::EditBuffer,
+    inspector::FIELDS,
+    misc::{get_byte_at_cursor, Parameters, TermState},
+};
+
+use super::{
+    bytes::{draw_inspector, draw_view},
+    Mode, Modes,
+};
+
+pub struct InspectorMode<'a> {
+    parameters: &'a Parameters,
+    selected: usize,
+    input: String,
+    error: Option<String>,
+}
+
+impl<'a> InspectorMode<'a> {
+    pub fn new(parameters: &'a Parameters) -> Self {
+        Self {
+            parameters,
+            selected: 0,
+            input: String::from(""),
+            error: None,
+        }
+    }
+
+    fn write(&mut self, state: &mut TermState) -> std::result::Result<(), String> {
+        let field = FIELDS[self.selected];
+        let bytes = field.parse(&self.input)?;
+
+        let byte_index = get_byte_at_cursor(state, self.parameters);
+        if byte_index + field.size() > state.buffer.len() {
+            return Err(format!("Not enough bytes left for {}", field.label()));
+        }
+
+        state.buffer.checkpoint(byte_index);
+        state.buffer.overwrite(byte_index, &bytes);
+
+        Ok(())
+    }
+}
+
+impl<'a> Mode for InspectorMode<'a> {
+    fn handle_input(
+        &mut self,
+        event: &crossterm::event::KeyEvent,
+        state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        self.error = None;
+
+        let end_mode = match event.code {
+            KeyCode::Esc => {
+                self.input.clear();
+                Modes::Bytes
+            }
+            KeyCode::Up => {
+                self.selected = self.selected.saturating_sub(1);
+                self.input.clear();
+                Modes::Inspector
+            }
+            KeyCode::Down => {
+                self.selected = (self.selected + 1).min(FIELDS.len() - 1);
+                self.input.clear();
+                Modes::Inspector
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+                Modes::Inspector
+            }
+            KeyCode::Char(char) => {
+                self.input.push(char);
+                Modes::Inspector
+            }
+            KeyCode::Enter => {
+                if self.input.is_empty() {
+                    return Ok(Modes::Inspector);
+                }
+
+                if let Err(error) = self.write(state) {
+                    self.error = Some(error);
+                    return Ok(Modes::Inspector);
+                }
+
+                self.input.clear();
+                Modes::Bytes
+            }
+            _ => Modes::Inspector,
+        };
+
+        Ok(end_mode)
+    }
+
+    fn handle_mouse(
+        &mut self,
+        _event: &crossterm::event::MouseEvent,
+        _state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        Ok(Modes::Inspector)
+    }
+
+    fn handle_resize(
+        &mut self,
+        _stdout: &mut std::io::Stdout,
+        width: u16,
+        height: u16,
+        state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        state.term_width = width;
+        state.term_height = height;
+        Ok(Modes::Inspector)
+    }
+
+    fn handle_paste(
+        &mut self,
+        text: &str,
+        _state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        self.input.push_str(text.trim());
+        Ok(Modes::Inspector)
+    }
+
+    fn draw(&self, stdout: &mut std::io::Stdout, state: &TermState) -> Result<()> {
+        draw_view(stdout, state, self.parameters)?;
+        draw_inspector(
+            stdout,
+            state,
+            self.parameters,
+            Some((self.selected, &self.input)),
+        )?;
+
+        let (msg, fg) = match &self.error {
+            Some(error) => (error.clone(), Color::Red),
+            None => (
+                format!("Edit {}: {}", FIELDS[self.selected].label(), self.input),
+                Color::DarkGrey,
+            ),
+        };
+
+        queue!(
+            stdout,
+            cursor::MoveTo(1, state.term_height),
+            terminal::Clear(ClearType::FromCursorDown),
+            style::SetForegroundColor(fg),
+            style::Print(msg),
+        )?;
+
+        Ok(())
+    }
+}
@@ -1,27 +1,57 @@
 use crossterm::event::{KeyEvent, MouseEvent};
-use std::io::{Stdout, Result};
+use std::io::{Result, Stdout};
 
 mod bytes;
 mod change;
+mod compare;
+mod confirm;
+mod export;
+mod external;
 mod goto;
 mod help;
+mod insert;
+mod inspector;
+mod paste;
+mod recover;
+mod register;
+mod save_as;
 mod search;
 
 pub use bytes::BytesMode;
 pub use change::ChangeMode;
+pub use compare::CompareMode;
+pub use confirm::ConfirmMode;
+pub use export::ExportMode;
+pub use external::ExternalMode;
 pub use goto::GoToMode;
 pub use help::HelpMode;
+pub use insert::InsertMode;
+pub use inspector::InspectorMode;
+pub use paste::PasteMode;
+pub use recover::RecoverMode;
+pub use register::RegisterMode;
+pub use save_as::SaveAsMode;
 pub use search::SearchMode;
 
 use crate::misc::{Parameters, TermState};
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy)]
 pub enum Modes {
     Bytes,
     Help,
     Change,
     GoTo,
     Search,
+    Insert,
+    Export,
+    Register,
+    Paste,
+    Inspector,
+    Confirm,
+    SaveAs,
+    Recover,
+    External,
+    Compare,
     Quit,
 }
 
@@ -46,5 +76,11 @@ pub trait Mode {
         state: &mut TermState,
         parameters: &Parameters,
     ) -> Result<Modes>;
+    fn handle_paste(
+        &mut self,
+        text: &str,
+        state: &mut TermState,
+        parameters: &Parameters,
+    ) -> Result<Modes>;
     fn draw(&self, stdout: &mut Stdout, state: &TermState) -> Result<()>;
 }
@@ -0,0 +1,129 @@
+use crossterm::{
+    cursor,
+    event::KeyCode,
+    queue,
+    style::{self, Color},
+    terminal::{self, ClearType},
+};
+
+use std::io::Result;
+
+use crate::{actions::paste_bytes, misc::DEFAULT_REGISTER, string::parse_hex};
+
+use super::{Mode, Modes};
+
+pub struct PasteMode {
+    insert: bool,
+    error: Option<String>,
+}
+
+impl PasteMode {
+    pub fn new() -> Self {
+        Self {
+            insert: false,
+            error: None,
+        }
+    }
+}
+
+impl Mode for PasteMode {
+    fn handle_input(
+        &mut self,
+        event: &crossterm::event::KeyEvent,
+        state: &mut crate::misc::TermState,
+        parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        self.error = None;
+
+        let bytes = match event.code {
+            KeyCode::Char('i') => {
+                self.insert = !self.insert;
+                return Ok(Modes::Paste);
+            }
+            KeyCode::Char('h') => match parse_hex(&state.pasted) {
+                Ok(bytes) => bytes,
+                Err(error) => {
+                    self.error = Some(error);
+                    return Ok(Modes::Paste);
+                }
+            },
+            KeyCode::Char('t') => state.pasted.as_bytes().to_vec(),
+            KeyCode::Esc | KeyCode::Char('q') => {
+                state.pasted.clear();
+                state.register = DEFAULT_REGISTER;
+                return Ok(Modes::Bytes);
+            }
+            _ => return Ok(Modes::Paste),
+        };
+
+        paste_bytes(state, parameters, &bytes, self.insert);
+        state.registers.insert(state.register, bytes);
+        state.register = DEFAULT_REGISTER;
+        state.pasted.clear();
+
+        Ok(Modes::Bytes)
+    }
+
+    fn handle_mouse(
+        &mut self,
+        _event: &crossterm::event::MouseEvent,
+        _state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        Ok(Modes::Paste)
+    }
+
+    fn handle_resize(
+        &mut self,
+        _stdout: &mut std::io::Stdout,
+        _width: u16,
+        _height: u16,
+        _state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        Ok(Modes::Paste)
+    }
+
+    fn handle_paste(
+        &mut self,
+        text: &str,
+        state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        state.pasted.push_str(text);
+        Ok(Modes::Paste)
+    }
+
+    fn draw(&self, stdout: &mut std::io::Stdout, state: &crate::misc::TermState) -> Result<()> {
+        if let Some(error) = &self.error {
+            queue!(
+                stdout,
+                cursor::MoveTo(1, state.term_height),
+                terminal::Clear(ClearType::FromCursorDown),
+                style::SetForegroundColor(Color::Red),
+                style::Print(error),
+            )?;
+            return Ok(());
+        }
+
+        let insert = match self.insert {
+            true => "on",
+            false => "off",
+        };
+        let msg = format!(
+            "Paste {} chars as (h)ex or (t)ext, (i)nsert: {}",
+            state.pasted.chars().count(),
+            insert
+        );
+
+        queue!(
+            stdout,
+            cursor::MoveTo(1, state.term_height),
+            terminal::Clear(ClearType::FromCursorDown),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(msg),
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use crossterm::{
+    cursor,
+    event::KeyCode,
+    queue,
+    style::{self, Color},
+    terminal::{self, ClearType},
+};
+
+use std::io::Result;
+
+use crate::{
+    misc::{Parameters, TermState},
+    swap::{recover, remove_swap},
+};
+
+use super::{bytes::draw_view, Mode, Modes};
+
+// Offered on startup when swap with unsaved edits of opened file is found
+pub struct RecoverMode<'a> {
+    parameters: &'a Parameters,
+    error: Option<String>,
+}
+
+impl<'a> RecoverMode<'a> {
+    pub fn new(parameters: &'a Parameters) -> Self {
+        Self {
+            parameters,
+            error: None,
+        }
+    }
+}
+
+impl<'a> Mode for RecoverMode<'a> {
+    fn handle_input(
+        &mut self,
+        event: &crossterm::event::KeyEvent,
+        state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        self.error = None;
+
+        let end_mode = match event.code {
+            KeyCode::Char('y') => {
+                if let Err(error) = recover(state) {
+                    self.error = Some(format!("Failed to recover: {}", error));
+                    return Ok(Modes::Recover);
+                }
+                Modes::Bytes
+            }
+            KeyCode::Char('n') => {
+                if let Err(error) = remove_swap(&state.file_path) {
+                    self.error = Some(format!("Failed to remove swap: {}", error));
+                    return Ok(Modes::Recover);
+                }
+                Modes::Bytes
+            }
+            _ => Modes::Recover,
+        };
+
+        Ok(end_mode)
+    }
+
+    fn handle_mouse(
+        &mut self,
+        _event: &crossterm::event::MouseEvent,
+        _state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        Ok(Modes::Recover)
+    }
+
+    fn handle_resize(
+        &mut self,
+        _stdout: &mut std::io::Stdout,
+        width: u16,
+        height: u16,
+        state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        state.term_width = width;
+        state.term_height = height;
+        Ok(Modes::Recover)
+    }
+
+    fn handle_paste(
+        &mut self,
+        _text: &str,
+        _state: &mut TermState,
+        _parameters: &Parameters,
+    ) -> Result<super::Modes> {
+        Ok(Modes::Recover)
+    }
+
+    fn draw(&self, stdout: &mut std::io::Stdout, state: &TermState) -> Result<()> {
+        draw_view(stdout, state, self.parameters)?;
+
+        let (msg, fg) = match &self.error {
+            Some(error) => (error.clone(), Color::Red),
+            None => (
+                String::from("Unsaved changes of this file were found. Recover them? (y/n)"),
+                Color::Yellow,
+            ),
+        };
+
+        queue!(
+            stdout,
+            cursor::MoveTo(1, state.term_height),
+            terminal::Clear(ClearType::FromCursorDown),
+            style::SetForegroundColor(fg),
+            style::Print(msg),
+        )?;
+
+        Ok(())
+    }
+}
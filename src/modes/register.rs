@@ -0,0 +1,82 @@
+use crossterm::{
+    cursor,
+    event::KeyCode,
+    queue,
+    style::{self, Color},
+    terminal::{self, ClearType},
+};
+
+use std::io::Result;
+
+use super::{Mode, Modes};
+
+pub struct RegisterMode {}
+
+impl RegisterMode {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Mode for RegisterMode {
+    fn handle_input(
+        &mut self,
+        event: &crossterm::event::KeyEvent,
+        state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        match event.code {
+            KeyCode::Char(char) if char.is_ascii_alphanumeric() || char == '"' => {
+                state.register = char;
+                Ok(Modes::Bytes)
+            }
+            KeyCode::Esc => Ok(Modes::Bytes),
+            _ => Ok(Modes::Register),
+        }
+    }
+
+    fn handle_mouse(
+        &mut self,
+        _event: &crossterm::event::MouseEvent,
+        _state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        Ok(Modes::Register)
+    }
+
+    fn handle_resize(
+        &mut self,
+        _stdout: &mut std::io::Stdout,
+        _width: u16,
+        _height: u16,
+        _state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        Ok(Modes::Register)
+    }
+
+    fn handle_paste(
+        &mut self,
+        _text: &str,
+        _state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        Ok(Modes::Register)
+    }
+
+    fn draw(&self, stdout: &mut std::io::Stdout, state: &crate::misc::TermState) -> Result<()> {
+        let mut used: Vec<&char> = state.registers.keys().collect();
+        used.sort();
+        let used: String = used.into_iter().collect();
+
+        queue!(
+            stdout,
+            cursor::MoveTo(1, state.term_height),
+            terminal::Clear(ClearType::FromCursorDown),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(format!("Register (used: {}): ", used)),
+        )?;
+
+        Ok(())
+    }
+}
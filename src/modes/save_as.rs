@@ -0,0 +1,170 @@
+use crossterm::{
+    cursor::{self, SetCursorStyle},
+    event::KeyCode,
+    queue,
+    style::{self, Color},
+    terminal::{self, ClearType},
+};
+
+use std::io::Result;
+
+use crate::{
+    actions::{open_buffer, write_buffer},
+    search_job,
+    swap::remove_swap,
+};
+
+use super::{Mode, Modes};
+
+pub struct SaveAsMode {
+    input: String,
+    error: Option<String>,
+    // File was written, waiting for decision whether to continue editing it
+    saved: bool,
+}
+
+impl SaveAsMode {
+    pub fn new() -> Self {
+        Self {
+            input: String::from(""),
+            error: None,
+            saved: false,
+        }
+    }
+
+    fn finish(&mut self) -> Modes {
+        self.input.clear();
+        self.saved = false;
+        Modes::Bytes
+    }
+}
+
+impl Mode for SaveAsMode {
+    fn handle_input(
+        &mut self,
+        event: &crossterm::event::KeyEvent,
+        state: &mut crate::misc::TermState,
+        parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        self.error = None;
+
+        if self.saved {
+            let end_mode = match event.code {
+                KeyCode::Char('y') => {
+                    let path = self.input.clone();
+                    let old_path = state.file_path.clone();
+                    if let Err(error) = open_buffer(state, &path) {
+                        self.error = Some(format!("Failed to open '{}': {}", path, error));
+                        return Ok(Modes::SaveAs);
+                    }
+                    // Edits of the old file went to the new one
+                    let _ = remove_swap(&old_path);
+                    state.selection_anchor = None;
+                    search_job::cancel(state);
+                    state.found_sequences.clear();
+                    self.finish()
+                }
+                KeyCode::Char('n') | KeyCode::Esc => self.finish(),
+                _ => Modes::SaveAs,
+            };
+            return Ok(end_mode);
+        }
+
+        let end_mode = match event.code {
+            KeyCode::Esc => self.finish(),
+            KeyCode::Backspace => {
+                self.input.pop();
+                Modes::SaveAs
+            }
+            KeyCode::Char(char) => {
+                self.input.push(char);
+                Modes::SaveAs
+            }
+            KeyCode::Enter => {
+                if self.input.is_empty() {
+                    return Ok(Modes::SaveAs);
+                }
+
+                if self.input == state.file_path {
+                    self.error = Some(String::from("Use save to write to the current file"));
+                    return Ok(Modes::SaveAs);
+                }
+
+                if let Err(error) = write_buffer(state, &self.input, parameters.backup) {
+                    self.error = Some(format!("Failed to save: {}", error));
+                    return Ok(Modes::SaveAs);
+                }
+
+                self.saved = true;
+                Modes::SaveAs
+            }
+            _ => Modes::SaveAs,
+        };
+
+        Ok(end_mode)
+    }
+
+    fn handle_mouse(
+        &mut self,
+        _event: &crossterm::event::MouseEvent,
+        _state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        Ok(Modes::SaveAs)
+    }
+
+    fn handle_resize(
+        &mut self,
+        _stdout: &mut std::io::Stdout,
+        width: u16,
+        height: u16,
+        state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        state.term_width = width;
+        state.term_height = height;
+        Ok(Modes::SaveAs)
+    }
+
+    fn handle_paste(
+        &mut self,
+        text: &str,
+        _state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        if !self.saved {
+            self.input.push_str(text.trim());
+        }
+        Ok(Modes::SaveAs)
+    }
+
+    fn draw(&self, stdout: &mut std::io::Stdout, state: &crate::misc::TermState) -> Result<()> {
+        if let Some(error) = &self.error {
+            queue!(
+                stdout,
+                cursor::MoveTo(1, state.term_height),
+                terminal::Clear(ClearType::FromCursorDown),
+                style::SetForegroundColor(Color::Red),
+                style::Print(error),
+            )?;
+            return Ok(());
+        }
+
+        let msg = match self.saved {
+            true => format!("Saved to: {}. Continue editing it? (y/n)", self.input),
+            false => format!("Save as: {}", self.input),
+        };
+
+        queue!(
+            stdout,
+            cursor::MoveTo(1, state.term_height),
+            terminal::Clear(ClearType::FromCursorDown),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(&msg),
+            SetCursorStyle::SteadyBlock,
+            cursor::MoveTo(1 + msg.len() as u16, state.term_height),
+        )?;
+
+        Ok(())
+    }
+}
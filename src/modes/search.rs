@@ -8,14 +8,23 @@ use crossterm::{
 
 use std::io::Result;
 
-use crate::string::naive_search;
+use crate::{
+    buffer::EditBuffer,
+    find::Overlap,
+    misc::get_selection,
+    search_job::{self, SearchJob},
+    string::{SearchKind, SEARCH_KINDS},
+};
 
 use super::{Mode, Modes};
 
 pub struct SearchMode {
     input: String,
     cursor: usize,
-    draw_error: bool,
+    error: Option<String>,
+    overlap: Overlap,
+    // Index in SEARCH_KINDS
+    kind: usize,
 }
 
 impl SearchMode {
@@ -23,7 +32,23 @@ impl SearchMode {
         Self {
             input: String::from(""),
             cursor: 13, //@Improve: base this value on msg length
-            draw_error: false,
+            error: None,
+            overlap: Overlap::Allowed,
+            kind: 0,
+        }
+    }
+
+    fn kind(&self) -> SearchKind {
+        SEARCH_KINDS[self.kind]
+    }
+
+    // Text typed for previous kind may not fit the new one
+    fn switch_kind(&mut self, kind: usize) {
+        self.kind = kind;
+        let kind = self.kind();
+        if !self.input.chars().all(|char| kind.accepts(char)) {
+            self.input.clear();
+            self.cursor = 13;
         }
     }
 }
@@ -35,10 +60,25 @@ impl Mode for SearchMode {
         state: &mut crate::misc::TermState,
         parameters: &crate::misc::Parameters,
     ) -> Result<super::Modes> {
-        if self.draw_error {
-            self.draw_error = false;
-        }
+        self.error = None;
+
         let end_mode = match event.code {
+            KeyCode::Esc => Modes::Bytes,
+            KeyCode::Up => {
+                self.switch_kind((self.kind + SEARCH_KINDS.len() - 1) % SEARCH_KINDS.len());
+                Modes::Search
+            }
+            KeyCode::Down => {
+                self.switch_kind((self.kind + 1) % SEARCH_KINDS.len());
+                Modes::Search
+            }
+            KeyCode::Tab => {
+                self.overlap = match self.overlap {
+                    Overlap::Allowed => Overlap::Skipped,
+                    Overlap::Skipped => Overlap::Allowed,
+                };
+                Modes::Search
+            }
             KeyCode::Right => {
                 self.cursor += 1;
                 Modes::Search
@@ -51,17 +91,18 @@ impl Mode for SearchMode {
                 Modes::Search
             }
             KeyCode::Backspace => {
-                if self.input.len() > 0 {
-                    self.input.remove(self.cursor - 14);
+                let removed = self.input.char_indices().nth(self.cursor.wrapping_sub(14));
+                if let Some((at, _)) = removed {
+                    self.input.remove(at);
                     self.cursor -= 1;
                 }
                 Modes::Search
             }
             KeyCode::Char(char) => {
-                if char == 'q' {
+                if char == 'q' && self.kind() == SearchKind::Hex {
                     return Ok(Modes::Bytes);
                 }
-                if !char.is_ascii_hexdigit() {
+                if !self.kind().accepts(char) {
                     return Ok(Modes::Search);
                 }
 
@@ -71,32 +112,28 @@ impl Mode for SearchMode {
                 Modes::Search
             }
             KeyCode::Enter => {
-                if self.input.len() == 0 {
+                if self.input.is_empty() {
                     return Ok(Modes::Search);
                 }
 
-                state.found_sequences.clear();
-
-                let mut bytes = vec![];
-
-                let mut counter = 0;
-                loop {
-                    if counter >= self.input.len() {
-                        break;
+                let query = match self.kind().query(&self.input) {
+                    Ok(query) => query,
+                    Err(error) => {
+                        self.error = Some(error);
+                        return Ok(Modes::Search);
                     }
+                };
 
-                    let offset = match counter + 1 < self.input.len() {
-                        true => 2,
-                        false => 1,
-                    };
-
-                    let slice = self.input.get(counter..counter + offset).unwrap();
-                    bytes.push(u8::from_str_radix(slice, 16).unwrap());
-
-                    counter += offset;
-                }
+                search_job::cancel(state);
+                state.found_sequences.clear();
 
-                state.found_sequences = naive_search(&bytes, &state.bytes);
+                let within = get_selection(state, parameters).unwrap_or(0..state.buffer.len());
+                let overlap = match self.kind() {
+                    SearchKind::Regex => Overlap::Skipped,
+                    _ => self.overlap,
+                };
+                state.search = Some(SearchJob::start(query, state, within, overlap));
+                state.selection_anchor = None;
 
                 Modes::Bytes
             }
@@ -108,34 +145,58 @@ impl Mode for SearchMode {
 
     fn handle_mouse(
         &mut self,
-        event: &crossterm::event::MouseEvent,
-        state: &mut crate::misc::TermState,
-        parameters: &crate::misc::Parameters,
+        _event: &crossterm::event::MouseEvent,
+        _state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
     ) -> Result<super::Modes> {
         Ok(Modes::Search)
     }
 
     fn handle_resize(
         &mut self,
-        stdout: &mut std::io::Stdout,
-        width: u16,
-        height: u16,
-        state: &mut crate::misc::TermState,
-        parameters: &crate::misc::Parameters,
+        _stdout: &mut std::io::Stdout,
+        _width: u16,
+        _height: u16,
+        _state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
     ) -> Result<super::Modes> {
         Ok(Modes::Search)
     }
 
+    fn handle_paste(
+        &mut self,
+        text: &str,
+        _state: &mut crate::misc::TermState,
+        _parameters: &crate::misc::Parameters,
+    ) -> Result<super::Modes> {
+        let kind = self.kind();
+        let accepted: Vec<char> = match kind {
+            SearchKind::Hex => text.chars().filter(|c| kind.accepts(*c)).collect(),
+            _ => text.trim_end_matches(['\r', '\n']).chars().collect(),
+        };
+        self.input.extend(&accepted);
+        self.cursor += accepted.len();
+        Ok(Modes::Search)
+    }
+
     fn draw(&self, stdout: &mut std::io::Stdout, state: &crate::misc::TermState) -> Result<()> {
         let msg = format!("Search for: {}", self.input);
+        let overlap = match self.overlap {
+            Overlap::Allowed => "overlapping",
+            Overlap::Skipped => "non-overlapping",
+        };
+        let options = match self.kind() {
+            SearchKind::Regex => format!("   (up/down) {}", self.kind().label()),
+            kind => format!("   (up/down) {}, (tab) {} matches", kind.label(), overlap),
+        };
 
-        if self.draw_error {
+        if let Some(error) = &self.error {
             queue!(
                 stdout,
                 cursor::MoveTo(1, state.term_height),
                 terminal::Clear(ClearType::FromCursorDown),
                 style::SetForegroundColor(Color::Red),
-                style::Print("Offset exceeds total number of offsets!"),
+                style::Print(error),
             )?;
         } else {
             queue!(
@@ -144,6 +205,8 @@ impl Mode for SearchMode {
                 terminal::Clear(ClearType::FromCursorDown),
                 style::SetForegroundColor(Color::DarkGrey),
                 style::Print(&msg),
+                style::SetForegroundColor(Color::Yellow),
+                style::Print(options),
                 SetCursorStyle::SteadyBlock,
                 cursor::MoveTo(self.cursor as u16, state.term_height),
             )?;
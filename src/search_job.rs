@@ -0,0 +1,131 @@
+use std::{
+    mem,
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+    },
+    thread,
+};
+
+use crate::{
+    find::{Overlap, Query},
+    misc::{StatusMode, TermState},
+    string::search_buffer,
+};
+
+// Patterns like "." match every byte, found ranges would take all memory of a big file
+const FOUND_LIMIT: usize = 1 << 22;
+
+struct Progress {
+    found: Vec<Range<usize>>,
+    scanned: usize,
+}
+
+// Search running on a worker thread, so that big files don't freeze the editor.
+// Matches are streamed back as the buffer is scanned.
+pub struct SearchJob {
+    receiver: Receiver<Progress>,
+    cancelled: Arc<AtomicBool>,
+    // Tab of searched file, user may switch to another one meanwhile
+    tab: usize,
+    pub scanned: usize,
+    pub total: usize,
+    pub found: usize,
+}
+
+impl SearchJob {
+    // Searches snapshot of the active file, edits made meanwhile are not taken into account
+    pub fn start(query: Query, state: &TermState, within: Range<usize>, overlap: Overlap) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let buffer = state.buffer.snapshot();
+        let total = within.len();
+
+        let stop = Arc::clone(&cancelled);
+        thread::spawn(move || {
+            search_buffer(&query, &buffer, within, overlap, &mut |found, scanned| {
+                // Editor gone or no longer interested
+                sender.send(Progress { found, scanned }).is_ok() && !stop.load(Ordering::Relaxed)
+            });
+        });
+
+        Self {
+            receiver,
+            cancelled,
+            tab: state.active_tab,
+            scanned: 0,
+            total,
+            found: 0,
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn percent(&self) -> usize {
+        match self.total {
+            0 => 100,
+            total => self.scanned * 100 / total,
+        }
+    }
+}
+
+// Moves matches found so far to the searched file, true when view has to be redrawn
+pub fn update(state: &mut TermState) -> bool {
+    let Some(job) = &mut state.search else {
+        return false;
+    };
+
+    let percent = job.percent();
+    let mut found = vec![];
+    let finished = loop {
+        match job.receiver.try_recv() {
+            Ok(mut progress) => {
+                job.scanned = progress.scanned;
+                found.append(&mut progress.found);
+            }
+            Err(TryRecvError::Empty) => break false,
+            Err(TryRecvError::Disconnected) => break true,
+        }
+    };
+
+    // Progress shown in status bar moves even when nothing is found
+    if found.is_empty() && !finished {
+        return job.percent() != percent;
+    }
+
+    let limited = job.found + found.len() > FOUND_LIMIT;
+    found.truncate(FOUND_LIMIT - job.found);
+    job.found += found.len();
+
+    let sequences = match job.tab == state.active_tab {
+        true => &mut state.found_sequences,
+        false => &mut state.tabs[job.tab].found_sequences,
+    };
+    sequences.append(&mut found);
+
+    if limited {
+        job.cancel();
+        state.status_mode =
+            StatusMode::Error(format!("Search stopped after {} matches", FOUND_LIMIT));
+    }
+    if finished || limited {
+        state.search = None;
+    }
+
+    true
+}
+
+// Stops running search, matches found until now are kept
+pub fn cancel(state: &mut TermState) -> bool {
+    match mem::take(&mut state.search) {
+        Some(job) => {
+            job.cancel();
+            true
+        }
+        None => false,
+    }
+}
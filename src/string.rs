@@ -1,31 +1,427 @@
-use std::{collections::HashSet, ops::Range};
+use std::ops::Range;
 
-pub fn naive_search(pattern: &Vec<u8>, text: &Vec<u8>) -> HashSet<Range<usize>> {
+use regex::bytes::RegexBuilder;
+
+use crate::{
+    buffer::EditBuffer,
+    find::{MaskedByte, Overlap, Query},
+};
+
+const SEARCH_WINDOW: usize = 1 << 20;
+
+// Buffer is scanned in windows overlapping by pattern length, so that big files
+// do not have to be read into memory all at once. Matches of every window are handed over
+// together with number of bytes scanned so far, search stops once `found` returns false.
+pub fn search_buffer(
+    query: &Query,
+    buffer: &dyn EditBuffer,
+    within: Range<usize>,
+    overlap: Overlap,
+    found: &mut dyn FnMut(Vec<Range<usize>>, usize) -> bool,
+) {
+    if query.is_empty() {
+        return;
+    }
+
+    let within_end = within.end.min(buffer.len());
+    let mut start = within.start;
+    let mut last_end: usize = 0;
+    while start < within_end {
+        let end = (start + SEARCH_WINDOW + query.len() - 1).min(within_end);
+
+        // Match reaching into this window from the previous one is not overlapped
+        let from = match overlap {
+            Overlap::Allowed => start,
+            Overlap::Skipped => last_end.clamp(start, end),
+        };
+
+        // Window edges are not where data starts or ends, unless they are the buffer edges
+        let context = query.context();
+        let read_start = from.saturating_sub(context);
+        let window = buffer.read(read_start..(end + context).min(buffer.len()));
+
+        // Regex matches starting past the window are found again by the next one,
+        // the ones reaching into context after it are cut at its end
+        let next_start = start + SEARCH_WINDOW;
+        let sequences: Vec<Range<usize>> = query
+            .find_all(&window, from - read_start, overlap)
+            .into_iter()
+            .map(|range| range.start + read_start..(range.end + read_start).min(end))
+            .filter(|range| range.start < next_start && !range.is_empty())
+            .collect();
+        last_end = sequences.last().map_or(last_end, |last| last.end);
+
+        start = next_start;
+        if !found(sequences, start.min(within_end) - within.start) {
+            return;
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SearchKind {
+    Hex,
+    Ascii,
+    AsciiIgnoreCase,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+    Regex,
+}
+
+pub const SEARCH_KINDS: [SearchKind; 8] = [
+    SearchKind::Hex,
+    SearchKind::Ascii,
+    SearchKind::AsciiIgnoreCase,
+    SearchKind::Utf8,
+    SearchKind::Utf16Le,
+    SearchKind::Utf16Be,
+    SearchKind::Latin1,
+    SearchKind::Regex,
+];
+
+impl SearchKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SearchKind::Hex => "hex",
+            SearchKind::Ascii => "ASCII",
+            SearchKind::AsciiIgnoreCase => "ASCII in any case",
+            SearchKind::Utf8 => "UTF-8",
+            SearchKind::Utf16Le => "UTF-16LE",
+            SearchKind::Utf16Be => "UTF-16BE",
+            SearchKind::Latin1 => "Latin-1",
+            SearchKind::Regex => "regex over bytes",
+        }
+    }
+
+    // Only hex pattern can be typed in hex search, any text in the others
+    pub fn accepts(&self, char: char) -> bool {
+        match self {
+            SearchKind::Hex => char.is_ascii_hexdigit() || "?()| ".contains(char),
+            _ => true,
+        }
+    }
+
+    // Bytes which text typed by user is stored as
+    pub fn query(&self, text: &str) -> Result<Query, String> {
+        let query = match self {
+            SearchKind::Hex => {
+                let pattern = parse_pattern(text)?;
+                // Would match at every offset and fill memory with found ranges
+                if pattern.iter().all(|byte| byte.is_any()) {
+                    return Err(String::from(
+                        "Pattern of wildcards only would match at every offset",
+                    ));
+                }
+                match pattern.iter().map(|byte| byte.fixed()).collect() {
+                    Some(bytes) => Query::Bytes(bytes),
+                    None => Query::Masked(pattern),
+                }
+            }
+            SearchKind::Ascii | SearchKind::AsciiIgnoreCase => {
+                if let Some(char) = text.chars().find(|char| !char.is_ascii()) {
+                    return Err(format!("'{}' is not an ASCII character", char));
+                }
+                match self {
+                    SearchKind::Ascii => Query::Bytes(text.as_bytes().to_vec()),
+                    _ => Query::IgnoreCase(text.as_bytes().to_vec()),
+                }
+            }
+            SearchKind::Utf8 => Query::Bytes(text.as_bytes().to_vec()),
+            SearchKind::Utf16Le => Query::Bytes(
+                text.encode_utf16()
+                    .flat_map(|unit| unit.to_le_bytes())
+                    .collect(),
+            ),
+            SearchKind::Utf16Be => Query::Bytes(
+                text.encode_utf16()
+                    .flat_map(|unit| unit.to_be_bytes())
+                    .collect(),
+            ),
+            SearchKind::Latin1 => {
+                let mut bytes = vec![];
+                for char in text.chars() {
+                    let byte = u8::try_from(char)
+                        .map_err(|_| format!("'{}' is not a Latin-1 character", char))?;
+                    bytes.push(byte);
+                }
+                Query::Bytes(bytes)
+            }
+            // Works on bytes, so "." matches any of them and "\x7f" stands for a single byte
+            SearchKind::Regex => {
+                let regex = RegexBuilder::new(text)
+                    .unicode(false)
+                    .dot_matches_new_line(true)
+                    .build()
+                    .map_err(|error| {
+                        // Syntax errors span lines pointing at the pattern, the last one explains
+                        let error = error.to_string();
+                        let reason = error.lines().last().unwrap_or_default();
+                        format!("Invalid regex: {}", reason.trim_start_matches("error: "))
+                    })?;
+                Query::Regex(regex)
+            }
+        };
+
+        Ok(query)
+    }
+}
+
+// Hex pattern like "4D 5A ?? ?? 50 45", "A? 3F" or "(4D|5A) 90", spaces are optional.
+// '?' stands for any nibble, alternatives in parentheses for any of the listed bytes.
+fn parse_pattern(text: &str) -> Result<Vec<MaskedByte>, String> {
+    let chars: Vec<char> = text.chars().filter(|char| !char.is_whitespace()).collect();
+    let mut pattern = vec![];
     let mut i = 0;
-    let mut j = 0;
-    let mut sequences = HashSet::new();
 
-    loop {
-        if i == pattern.len() {
-            let range = (j + i - 1) - (pattern.len() - 1)..j + i;
-            sequences.insert(range);
-            i = 0;
-            j += 1;
+    while i < chars.len() {
+        if chars[i] != '(' {
+            pattern.push(MaskedByte {
+                alternatives: vec![masked_byte(&chars, i)?],
+            });
+            i += 2;
+            continue;
+        }
+
+        let mut alternatives = vec![masked_byte(&chars, i + 1)?];
+        i += 3;
+        loop {
+            match chars.get(i) {
+                Some('|') => {
+                    alternatives.push(masked_byte(&chars, i + 1)?);
+                    i += 3;
+                }
+                Some(')') => break,
+                _ => return Err(String::from("Alternatives have to be closed with ')'")),
+            }
         }
+        pattern.push(MaskedByte { alternatives });
+        i += 1;
+    }
+
+    Ok(pattern)
+}
+
+// Two nibbles starting at i, each either hex digit or '?'
+fn masked_byte(chars: &[char], i: usize) -> Result<(u8, u8), String> {
+    let nibble = |i: usize| match chars.get(i) {
+        Some('?') => Ok((0, 0)),
+        Some(char) => char
+            .to_digit(16)
+            .map(|digit| (digit as u8, 0xF))
+            .ok_or_else(|| format!("'{}' is not a hex digit or '?'", char)),
+        None => Err(String::from("Half of a byte is missing at the end")),
+    };
 
-        if j >= text.len() || i + j >= text.len() {
-            break;
+    let (high, high_mask) = nibble(i)?;
+    let (low, low_mask) = nibble(i + 1)?;
+    Ok((high << 4 | low, high_mask << 4 | low_mask))
+}
+
+// Accepts bytes written like "DE AD BE EF", "DEADBEEF", "0xde,0xad" or "\xde\xad".
+// Single digit is a byte on its own, as in "0xA", longer runs are read as pairs of digits,
+// so odd one like "ABC" can't be told apart from "0A BC" or "AB 0C" and is rejected.
+pub fn parse_hex(text: &str) -> Result<Vec<u8>, String> {
+    let normalized = text
+        .replace("\\x", " ")
+        .replace("0x", " ")
+        .replace("0X", " ");
+    let mut bytes = vec![];
+
+    for token in normalized.split(|c: char| c.is_whitespace() || c == ',' || c == ';') {
+        if token.is_empty() {
+            continue;
         }
 
-        let byte_to_match = pattern[i];
-        let byte = &text[j + i];
+        if !token.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("'{}' is not a hex byte", token));
+        }
+
+        if token.len() == 1 {
+            bytes.push(u8::from_str_radix(token, 16).unwrap());
+            continue;
+        }
+
+        if token.len() % 2 != 0 {
+            return Err(format!(
+                "'{}' has odd number of digits, separate its bytes",
+                token
+            ));
+        }
+
+        for i in (0..token.len()).step_by(2) {
+            bytes.push(u8::from_str_radix(&token[i..i + 2], 16).unwrap());
+        }
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        buffer::{Origin, PieceTable},
+        testing::Random,
+    };
+
+    fn byte(alternatives: &[(u8, u8)]) -> MaskedByte {
+        MaskedByte {
+            alternatives: alternatives.to_vec(),
+        }
+    }
+
+    #[test]
+    fn pasted_hex_formats() {
+        let dead_beef = Ok(vec![0xDE, 0xAD, 0xBE, 0xEF]);
+        assert_eq!(parse_hex("DE AD BE EF"), dead_beef);
+        assert_eq!(parse_hex("deadbeef\n"), dead_beef);
+        assert_eq!(parse_hex("0xde,0xad, 0xBE,0xef"), dead_beef);
+        assert_eq!(parse_hex("\\xde\\xad\\xbe\\xef"), dead_beef);
+        assert_eq!(parse_hex("0x1, 0xA"), Ok(vec![0x01, 0x0A]));
+        assert_eq!(parse_hex(""), Ok(vec![]));
+
+        assert!(parse_hex("ABC")
+            .unwrap_err()
+            .contains("odd number of digits"));
+        assert!(parse_hex("DE AG").unwrap_err().contains("not a hex byte"));
+    }
+
+    #[test]
+    fn hex_patterns() {
+        assert_eq!(
+            parse_pattern("4D 5a ?? A? ?3"),
+            Ok(vec![
+                byte(&[(0x4D, 0xFF)]),
+                byte(&[(0x5A, 0xFF)]),
+                byte(&[(0x00, 0x00)]),
+                byte(&[(0xA0, 0xF0)]),
+                byte(&[(0x03, 0x0F)]),
+            ])
+        );
+        assert_eq!(
+            parse_pattern("(4D|5?)90"),
+            Ok(vec![
+                byte(&[(0x4D, 0xFF), (0x50, 0xF0)]),
+                byte(&[(0x90, 0xFF)])
+            ])
+        );
+
+        assert!(matches!(
+            SearchKind::Hex.query("de ad"),
+            Ok(Query::Bytes(bytes)) if bytes == [0xDE, 0xAD]
+        ));
+        assert!(matches!(
+            SearchKind::Hex.query("de ?d"),
+            Ok(Query::Masked(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_hex_patterns_are_explained() {
+        let error = |text: &str| SearchKind::Hex.query(text).unwrap_err();
+
+        assert_eq!(error("4D5"), "Half of a byte is missing at the end");
+        assert_eq!(error("(4D|5"), "Half of a byte is missing at the end");
+        assert_eq!(error("4G"), "'G' is not a hex digit or '?'");
+        assert_eq!(error("(4D|50"), "Alternatives have to be closed with ')'");
+        assert_eq!(error("(4D 50)"), "Alternatives have to be closed with ')'");
+        assert_eq!(
+            error("?? (4?|??)"),
+            "Pattern of wildcards only would match at every offset"
+        );
+    }
+
+    fn naive(pattern: &[MaskedByte], text: &[u8], overlap: Overlap) -> Vec<Range<usize>> {
+        let mut found: Vec<Range<usize>> = vec![];
+        for start in 0..(text.len() + 1).saturating_sub(pattern.len()) {
+            let matches = pattern.iter().zip(&text[start..]).all(|(masked, byte)| {
+                masked
+                    .alternatives
+                    .iter()
+                    .any(|(value, mask)| byte & mask == *value)
+            });
+            let overlapped = found.last().is_some_and(|last| last.end > start);
+
+            if matches && (overlap == Overlap::Allowed || !overlapped) {
+                found.push(start..start + pattern.len());
+            }
+        }
+        found
+    }
+
+    #[test]
+    fn masked_search_matches_naive_one() {
+        let mut random = Random(11);
+        // Small alphabet, so that patterns are found often
+        let text: Vec<u8> = (0..4000)
+            .map(|_| [0x00, 0x0F, 0xA5, 0xAF][random.below(4)])
+            .collect();
+
+        for _ in 0..100 {
+            let pattern: Vec<MaskedByte> = (0..1 + random.below(6))
+                .map(|_| {
+                    let alternatives = (0..1 + random.below(2))
+                        .map(|_| {
+                            let mask = [0x00, 0x0F, 0xF0, 0xFF][random.below(4)];
+                            (text[random.below(text.len())] & mask, mask)
+                        })
+                        .collect();
+                    MaskedByte { alternatives }
+                })
+                .collect();
+
+            for overlap in [Overlap::Allowed, Overlap::Skipped] {
+                let query = Query::Masked(pattern.clone());
+                assert_eq!(
+                    query.find_all(&text, 0, overlap),
+                    naive(&pattern, &text, overlap),
+                    "{:?}",
+                    pattern
+                );
+            }
+        }
+    }
+
+    fn search(query: &Query, buffer: &PieceTable) -> Vec<Range<usize>> {
+        let mut all = vec![];
+        search_buffer(
+            query,
+            buffer,
+            0..buffer.len(),
+            Overlap::Skipped,
+            &mut |found, _| {
+                all.extend(found);
+                true
+            },
+        );
+        all
+    }
+
+    #[test]
+    fn regex_windows_are_not_data_edges() {
+        let mut bytes = vec![b'.'; 3 * SEARCH_WINDOW];
+        for at in [0, SEARCH_WINDOW - 2, 2 * SEARCH_WINDOW, bytes.len() - 4] {
+            bytes[at..at + 4].copy_from_slice(b"\x7fELF");
+        }
+        let buffer = PieceTable::new(Origin::Memory(bytes.clone()));
 
-        if *byte == byte_to_match {
-            i += 1;
-        } else {
-            i = 0;
-            j += 1;
+        for pattern in [
+            r"^\x7fELF",
+            r"\x7fELF$",
+            r"\bELF",
+            r"\x7fE",
+            r"LF\.\.",
+            r"\.+",
+        ] {
+            let query = SearchKind::Regex.query(pattern).unwrap();
+            assert_eq!(
+                search(&query, &buffer),
+                query.find_all(&bytes, 0, Overlap::Skipped),
+                "{}",
+                pattern
+            );
         }
     }
-    sequences
 }
@@ -0,0 +1,191 @@
+use directories::ProjectDirs;
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    buffer::{EditBuffer, PieceTable},
+    misc::{open_files, TermState},
+};
+
+const MAGIC: &[u8; 8] = b"HEXRSSWP";
+const SWAP_INTERVAL: Duration = Duration::from_secs(5);
+
+// Same file opened through different relative paths shares one swap
+fn full_path(file_path: &str) -> String {
+    fs::canonicalize(file_path)
+        .unwrap_or_else(|_| PathBuf::from(file_path))
+        .to_string_lossy()
+        .to_string()
+}
+
+// Swap files live next to the keys config, one per edited file
+pub fn swap_path(file_path: &str) -> Option<PathBuf> {
+    let config_path = ProjectDirs::from("com", "Papilionem", "Hex editor")?;
+
+    let name: String = full_path(file_path)
+        .chars()
+        .map(|char| match char {
+            '/' | '\\' | ':' => '%',
+            _ => char,
+        })
+        .collect();
+
+    let mut path = config_path.config_dir().join("swap");
+    path.push(format!("{}.swp", name));
+    Some(path)
+}
+
+// Swap is only trusted when it was made for the same file of the same size
+fn open_swap(state: &TermState) -> io::Result<BufReader<File>> {
+    let path = swap_path(&state.file_path)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No config dir"))?;
+    let mut reader = BufReader::new(File::open(path)?);
+
+    let mismatch = || io::Error::new(io::ErrorKind::InvalidData, "Swap belongs to another file");
+    let expected_path = full_path(&state.file_path);
+
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+
+    let mut len = [0; 8];
+    reader.read_exact(&mut len)?;
+    if &magic != MAGIC || u64::from_le_bytes(len) != expected_path.len() as u64 {
+        return Err(mismatch());
+    }
+
+    let mut file_path = vec![0; expected_path.len()];
+    reader.read_exact(&mut file_path)?;
+
+    let mut size = [0; 8];
+    reader.read_exact(&mut size)?;
+
+    if file_path != expected_path.as_bytes()
+        || u64::from_le_bytes(size) != state.buffer.original_len() as u64
+    {
+        return Err(mismatch());
+    }
+
+    Ok(reader)
+}
+
+pub fn is_recoverable(state: &TermState) -> bool {
+    open_swap(state).is_ok()
+}
+
+pub fn recover(state: &mut TermState) -> io::Result<()> {
+    let mut reader = open_swap(state)?;
+    state.buffer.read_journal(&mut reader)
+}
+
+pub fn remove_swap(file_path: &str) -> io::Result<()> {
+    match swap_path(file_path) {
+        Some(path) if path.exists() => fs::remove_file(path),
+        _ => Ok(()),
+    }
+}
+
+fn write_swap(file_path: &str, buffer: &PieceTable, path: &PathBuf) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    // Half written swap must not replace the previous one
+    let temp_path = path.with_extension("swp.tmp");
+    let mut writer = BufWriter::new(File::create(&temp_path)?);
+
+    let file_path = full_path(file_path);
+    let file_path = file_path.as_bytes();
+    writer.write_all(MAGIC)?;
+    writer.write_all(&(file_path.len() as u64).to_le_bytes())?;
+    writer.write_all(file_path)?;
+    writer.write_all(&(buffer.original_len() as u64).to_le_bytes())?;
+    buffer.write_journal(&mut writer)?;
+
+    drop(writer);
+    fs::rename(temp_path, path)
+}
+
+// Periodically journals unsaved edits of every open file, so they survive crash of the editor
+// or terminal. Files in background tabs are included, they may hold edits made before switching.
+pub struct SwapWriter {
+    // Buffer revision each swap holds
+    written: HashMap<PathBuf, usize>,
+    // Swaps of buffers changed in this session, others are left for the user to decide on
+    owned: HashSet<PathBuf>,
+    last_write: Instant,
+}
+
+impl SwapWriter {
+    pub fn new(state: &TermState) -> Self {
+        // Swaps found on startup are left alone until their buffer changes
+        let written = open_files(state)
+            .into_iter()
+            .filter_map(|(file_path, buffer)| Some((swap_path(file_path)?, buffer.revision())))
+            .collect();
+
+        Self {
+            written,
+            owned: HashSet::new(),
+            last_write: Instant::now(),
+        }
+    }
+
+    pub fn update(&mut self, state: &TermState) -> io::Result<()> {
+        if self.last_write.elapsed() < SWAP_INTERVAL {
+            return Ok(());
+        }
+        self.last_write = Instant::now();
+
+        // One failing swap does not keep the others from being written
+        let mut result = Ok(());
+        for (file_path, buffer) in open_files(state) {
+            let Some(path) = swap_path(file_path) else {
+                continue;
+            };
+            let revision = buffer.revision();
+
+            if self.written.get(&path) == Some(&revision) {
+                continue;
+            }
+            self.owned.insert(path.clone());
+
+            let written = match buffer.is_modified() {
+                true => write_swap(file_path, buffer, &path),
+                false if path.exists() => fs::remove_file(&path),
+                false => Ok(()),
+            };
+            match written {
+                Ok(()) => {
+                    self.written.insert(path, revision);
+                }
+                Err(error) => result = result.and(Err(error)),
+            }
+        }
+
+        result
+    }
+
+    // Leaving on purpose, swaps of buffers changed in this session hold nothing worth keeping.
+    // Others were found on startup and the user was not asked about them yet.
+    pub fn remove_owned(&mut self, state: &TermState) -> io::Result<()> {
+        for (file_path, buffer) in open_files(state) {
+            if let Some(path) = swap_path(file_path) {
+                if self.written.get(&path) != Some(&buffer.revision()) {
+                    self.owned.insert(path);
+                }
+            }
+        }
+
+        for path in &self.owned {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,15 @@
+// Deterministic xorshift for randomized tests, so failures can be reproduced from the seed
+pub struct Random(pub u64);
+
+impl Random {
+    pub fn below(&mut self, max: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % max.max(1) as u64) as usize
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.below(256) as u8).collect()
+    }
+}